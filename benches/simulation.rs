@@ -0,0 +1,77 @@
+//!
+//! File:           simulation.rs
+//! Description:    Benchmarks of the simulation hot path
+//!
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rust_blackjack::data::simulation::run_automated_match;
+use rust_blackjack::{Deck, SimConfig};
+
+/// Heap allocations made so far
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator, counting every allocation so benchmarks can report them next to the timings
+struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Prints how many heap allocations a single call to `f` makes
+fn report_allocations<T>(name: &str, f: impl FnOnce() -> T) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    black_box(f());
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!("{:<24}allocations: {}", name, allocations);
+}
+
+/// Building and shuffling a fresh deck, which simulations do for the first game of a run
+fn deck_creation(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0);
+    report_allocations("deck: new + shuffle", || Deck::with_rng(1, &mut rng));
+    c.bench_function("deck: new + shuffle", |b| {
+        b.iter(|| Deck::with_rng(black_box(1), &mut rng))
+    });
+}
+
+/// Refilling and shuffling an existing deck in place, which simulations do between games
+fn deck_reset(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut deck = Deck::with_rng(1, &mut rng);
+    report_allocations("deck: reset", || deck.reset_with(&mut rng));
+    c.bench_function("deck: reset", |b| {
+        b.iter(|| black_box(&mut deck).reset_with(&mut rng))
+    });
+}
+
+/// A whole session of a basic strategy player, with the default number of games
+fn full_session(c: &mut Criterion) {
+    let config = SimConfig::default();
+    report_allocations("session: default config", || {
+        run_automated_match(&config, 0)
+    });
+    let mut seed = 0;
+    c.bench_function("session: default config", |b| {
+        b.iter(|| {
+            seed += 1;
+            run_automated_match(&config, black_box(seed))
+        })
+    });
+}
+
+criterion_group!(benches, deck_creation, deck_reset, full_session);
+criterion_main!(benches);
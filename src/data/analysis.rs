@@ -0,0 +1,120 @@
+//!
+//! File:           analysis.rs
+//! Description:    Analytics built on top of the game simulation
+//!
+
+use rstest::rstest;
+use std::io;
+
+#[cfg(test)]
+use crate::types::card::Suit;
+use crate::types::card::{Card, Rank, MAX_BLACKJACK};
+use crate::types::deck::Deck;
+use crate::types::hand::{Hand, Strategy, DEFAULT_BET_VALUE};
+
+/// Number of buckets in a final total distribution: totals 0 through 21, plus 1 bucket for busts.
+pub const FINAL_TOTAL_BUCKETS: usize = MAX_BLACKJACK + 2;
+/// Index of the bust bucket in a final total distribution.
+pub const BUST_BUCKET: usize = MAX_BLACKJACK + 1;
+/// Number of simulated hands used to estimate a distribution.
+const DISTRIBUTION_TRIALS: usize = 10_000;
+
+/// Estimates the probability of each final total for a player starting with `start_hand` against the
+/// dealer's `up_card`, playing out the given (automated) `strategy` with cards drawn from `deck`.
+/// Index `i` holds the probability of finishing on a total of `i`; `BUST_BUCKET` holds busts.
+pub fn final_total_distribution(
+    start_hand: &[Card],
+    up_card: Rank,
+    strategy: Strategy,
+    deck: &Deck,
+) -> [f64; FINAL_TOTAL_BUCKETS] {
+    if strategy == Strategy::Human {
+        panic!("Final total distributions can only be computed for automated strategies.")
+    }
+
+    let mut counts = [0usize; FINAL_TOTAL_BUCKETS];
+    for _ in 0..DISTRIBUTION_TRIALS {
+        let mut trial_deck = deck.clone();
+        trial_deck.shuffle();
+        let mut hand = Hand::from_vector("Analysis", strategy.clone(), start_hand.to_vec());
+        loop {
+            let (stop, _) = hand.play_once_from(
+                &mut trial_deck,
+                DEFAULT_BET_VALUE,
+                up_card,
+                &mut io::empty(),
+            );
+            if stop {
+                break;
+            }
+        }
+        counts[hand.final_value().min(BUST_BUCKET)] += 1;
+    }
+
+    let mut distribution = [0f64; FINAL_TOTAL_BUCKETS];
+    for (i, count) in counts.iter().enumerate() {
+        distribution[i] = *count as f64 / DISTRIBUTION_TRIALS as f64;
+    }
+    distribution
+}
+
+/// A player on hard 20 (almost) never draws another card under basic strategy
+#[rstest]
+fn hard_twenty_stays_twenty() {
+    let start_hand = vec![
+        Card {
+            suit: Suit::Hearts,
+            rank: Rank::Ten,
+        },
+        Card {
+            suit: Suit::Clubs,
+            rank: Rank::Queen,
+        },
+    ];
+    let distribution = final_total_distribution(
+        &start_hand,
+        Rank::Six,
+        Strategy::ProbabilityTable,
+        &Deck::new(),
+    );
+    assert!(distribution[20] > 0.99);
+    assert!((distribution.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+}
+
+/// Estimates the average number of hands a flat bettor survives before going broke, using a
+/// random-walk (diffusion) approximation. The house edge drains the bankroll at a steady rate, ruining
+/// it in roughly `bankroll / (edge * bet)` hands, while the variance of each hand can drain it on a
+/// time scale of `bankroll^2 / (variance * bet^2)` hands. Both rates act together, so they are summed.
+/// `house_edge` is a fraction of the bet (i.e. 0.005 for 0.5%) and `variance_per_hand` is in units of
+/// bets squared (roughly 1.3 for BlackJack).
+pub fn expected_hands_to_ruin(
+    bankroll: isize,
+    bet: isize,
+    house_edge: f64,
+    variance_per_hand: f64,
+) -> f64 {
+    if bankroll <= 0 {
+        return 0.0;
+    }
+    let bankroll = bankroll as f64;
+    let bet = bet as f64;
+    // A player advantage never drifts towards ruin
+    let drift_rate = (house_edge * bet).max(0.0) / bankroll;
+    let diffusion_rate = variance_per_hand * bet * bet / (bankroll * bankroll);
+    let ruin_rate = drift_rate + diffusion_rate;
+    if ruin_rate <= 0.0 {
+        return f64::INFINITY;
+    }
+    1.0 / ruin_rate
+}
+
+/// A larger bankroll or a smaller house edge survives longer
+#[rstest]
+fn ruin_survival_trends() {
+    let base = expected_hands_to_ruin(100, 1, 0.005, 1.3);
+    assert!(base > 0.0);
+    assert!(expected_hands_to_ruin(200, 1, 0.005, 1.3) > base);
+    assert!(expected_hands_to_ruin(100, 1, 0.001, 1.3) > base);
+    assert!(expected_hands_to_ruin(100, 5, 0.005, 1.3) < base);
+    assert_eq!(expected_hands_to_ruin(0, 1, 0.005, 1.3), 0.0);
+}
@@ -0,0 +1,77 @@
+//!
+//! File:           chart.rs
+//! Description:    Renders the basic strategy chart, derived from the probability table
+//!
+
+use rstest::rstest;
+use std::fmt::Write;
+
+use crate::data::probability_table::{get_action, Action};
+use crate::types::card::Rank;
+
+/// Lowest player total shown in the chart
+pub const CHART_MIN_TOTAL: usize = 5;
+/// Highest player total shown in the chart
+pub const CHART_MAX_TOTAL: usize = 20;
+
+/// Dealer up cards shown as chart columns. Face cards play identically to a ten.
+pub fn chart_up_cards() -> Vec<Rank> {
+    Rank::iter()
+        .filter(|r| !matches!(r, Rank::Jack | Rank::Queen | Rank::King))
+        .copied()
+        .collect()
+}
+
+/// Returns the CSS class and chart abbreviation for an action
+pub fn action_cell(action: &Action) -> (&'static str, &'static str) {
+    match action {
+        Action::Hit => ("hit", "H"),
+        Action::Stand => ("stand", "S"),
+        Action::DoubleDown => ("double", "D"),
+        Action::Split => ("split", "P"),
+        Action::Surrender => ("surrender", "R"),
+    }
+}
+
+/// Renders the basic strategy chart as a color-coded HTML page. Rows are player totals and columns
+/// are dealer up cards.
+pub fn strategy_chart_html() -> String {
+    let up_cards = chart_up_cards();
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<title>Basic Strategy</title>\n<style>\n");
+    html.push_str("td, th { padding: 4px 8px; text-align: center; }\n");
+    html.push_str(".hit { background-color: #e57373; }\n");
+    html.push_str(".stand { background-color: #81c784; }\n");
+    html.push_str(".double { background-color: #ffd54f; }\n");
+    html.push_str(".split { background-color: #64b5f6; }\n");
+    html.push_str(".surrender { background-color: #bdbdbd; }\n");
+    html.push_str("</style>\n</head>\n<body>\n<table>\n<tr><th></th>");
+    for up_card in up_cards.iter() {
+        write!(html, "<th>{}</th>", up_card).expect("I/O Error");
+    }
+    html.push_str("</tr>\n");
+
+    for total in CHART_MIN_TOTAL..=CHART_MAX_TOTAL {
+        write!(html, "<tr><th>{}</th>", total).expect("I/O Error");
+        for up_card in up_cards.iter() {
+            let (class, label) = action_cell(&get_action(total, false, *up_card));
+            write!(
+                html,
+                "<td class=\"{}\" data-total=\"{}\" data-up=\"{}\">{}</td>",
+                class, total, up_card, label
+            )
+            .expect("I/O Error");
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+/// The chart is derived from the probability table, so 16 vs 10 is a hit
+#[rstest]
+fn chart_hit_sixteen_vs_ten() {
+    let html = strategy_chart_html();
+    assert!(html.contains("<td class=\"hit\" data-total=\"16\" data-up=\"10\">H</td>"));
+    assert!(html.contains("<td class=\"double\" data-total=\"11\" data-up=\"6\">D</td>"));
+}
@@ -0,0 +1,342 @@
+//!
+//! File:           ev.rs
+//! Description:    Expected value (EV) of player decisions, assuming an infinite deck
+//!
+use rayon::prelude::*;
+use rstest::rstest;
+use std::cmp::Ordering;
+
+use crate::data::probability_table::Action;
+#[cfg(test)]
+use crate::types::card::Suit;
+use crate::types::card::{Card, Rank, MAX_BLACKJACK};
+use crate::types::hand::DEALER_HAND_THRESHOLD;
+use crate::types::rules::Rules;
+
+/// Number of distinct dealer results: 17 through 21, then bust.
+pub const DEALER_OUTCOMES: usize = 6;
+/// Index of the bust result in a dealer distribution
+pub const DEALER_BUST_IDX: usize = 5;
+/// Counting an Ace as 11 adds this much to the hand
+const SOFT_ACE_BONUS: usize = 10;
+
+/// Odds of drawing a card worth `value` (an Ace is worth 1) from an infinite deck
+fn draw_probability(value: usize) -> f64 {
+    match value {
+        10 => 4.0 / 13.0,
+        _ => 1.0 / 13.0,
+    }
+}
+
+/// Best total of a hand, counting an Ace as 11 when it doesn't bust
+fn best_total(lo_sum: usize, has_ace: bool) -> usize {
+    if has_ace && lo_sum + SOFT_ACE_BONUS <= MAX_BLACKJACK {
+        return lo_sum + SOFT_ACE_BONUS;
+    }
+    lo_sum
+}
+
+/// Value of a rank when counting an Ace as 1
+fn low_value(rank: Rank) -> usize {
+    match rank {
+        Rank::Ace => 1,
+        _ => rank.value(),
+    }
+}
+
+/// Distribution of the dealer's results from a partial hand
+fn dealer_from(lo_sum: usize, has_ace: bool, rules: &Rules) -> [f64; DEALER_OUTCOMES] {
+    let mut dist = [0.0; DEALER_OUTCOMES];
+    let total = best_total(lo_sum, has_ace);
+    if lo_sum > MAX_BLACKJACK {
+        dist[DEALER_BUST_IDX] = 1.0;
+        return dist;
+    }
+    let soft_17 = total == DEALER_HAND_THRESHOLD && total != lo_sum;
+    if total >= DEALER_HAND_THRESHOLD && !(soft_17 && rules.dealer_hits_soft_17) {
+        dist[total - DEALER_HAND_THRESHOLD] = 1.0;
+        return dist;
+    }
+    for value in 1..=10 {
+        let next = dealer_from(lo_sum + value, has_ace || value == 1, rules);
+        for (d, n) in dist.iter_mut().zip(next.iter()) {
+            *d += draw_probability(value) * n;
+        }
+    }
+    dist
+}
+
+/// Distribution of the dealer's results (17 through 21, then bust) showing `up_card`. The dealer
+/// checks for a natural before the player acts, so the hole card never makes one.
+pub fn dealer_distribution(up_card: Rank, rules: &Rules) -> [f64; DEALER_OUTCOMES] {
+    let up_value = low_value(up_card);
+    let natural_card = match up_value {
+        1 => 10,
+        10 => 1,
+        _ => 0,
+    };
+    let no_natural = match natural_card {
+        0 => 1.0,
+        card => 1.0 - draw_probability(card),
+    };
+
+    let mut dist = [0.0; DEALER_OUTCOMES];
+    for value in (1..=10).filter(|v| *v != natural_card) {
+        let next = dealer_from(up_value + value, up_value == 1 || value == 1, rules);
+        for (d, n) in dist.iter_mut().zip(next.iter()) {
+            *d += draw_probability(value) / no_natural * n;
+        }
+    }
+    dist
+}
+
+/// EV of standing on `total` against the dealer's result distribution
+fn stand_ev(total: usize, dealer: &[f64; DEALER_OUTCOMES]) -> f64 {
+    if total > MAX_BLACKJACK {
+        return -1.0;
+    }
+    let mut ev = dealer[DEALER_BUST_IDX];
+    for (idx, p) in dealer[..DEALER_BUST_IDX].iter().enumerate() {
+        let dealer_total = DEALER_HAND_THRESHOLD + idx;
+        ev += match total.cmp(&dealer_total) {
+            Ordering::Greater => *p,
+            Ordering::Equal => 0.0,
+            Ordering::Less => -p,
+        };
+    }
+    ev
+}
+
+/// EV of playing on (hitting or standing, whichever is better) for every hand. Indexed by the low
+/// sum of the hand and whether it holds an Ace.
+fn play_on_evs(dealer: &[f64; DEALER_OUTCOMES]) -> [[f64; 2]; MAX_BLACKJACK + 1] {
+    let mut evs = [[0.0; 2]; MAX_BLACKJACK + 1];
+    for lo_sum in (1..=MAX_BLACKJACK).rev() {
+        for has_ace in [false, true] {
+            let stand = stand_ev(best_total(lo_sum, has_ace), dealer);
+            let hit = hit_ev(lo_sum, has_ace, &evs);
+            evs[lo_sum][has_ace as usize] = stand.max(hit);
+        }
+    }
+    evs
+}
+
+/// EV of taking one card and then playing on, given the EVs of every larger hand
+fn hit_ev(lo_sum: usize, has_ace: bool, evs: &[[f64; 2]; MAX_BLACKJACK + 1]) -> f64 {
+    (1..=10)
+        .map(|value| {
+            let next = lo_sum + value;
+            let ev = match next {
+                n if n > MAX_BLACKJACK => -1.0,
+                n => evs[n][(has_ace || value == 1) as usize],
+            };
+            draw_probability(value) * ev
+        })
+        .sum()
+}
+
+/// EV of doubling the bet and taking exactly one more card
+fn double_ev(lo_sum: usize, has_ace: bool, dealer: &[f64; DEALER_OUTCOMES]) -> f64 {
+    (1..=10)
+        .map(|value| {
+            let total = best_total(lo_sum + value, has_ace || value == 1);
+            draw_probability(value) * 2.0 * stand_ev(total, dealer)
+        })
+        .sum()
+}
+
+/// Finds the best action for the player's `cards` against the dealer's `up_card`, along with its EV
+/// (in units of the original bet). Doubling and surrendering are only considered on the opening two
+/// cards. Splitting is not considered.
+pub fn evaluate_spot(cards: &[Card], up_card: Rank, rules: &Rules) -> (Action, f64) {
+    let lo_sum: usize = cards.iter().map(|c| low_value(c.rank)).sum();
+    let has_ace = cards.iter().any(|c| c.rank == Rank::Ace);
+    let total = best_total(lo_sum, has_ace);
+    let opening = cards.len() == 2;
+
+    if opening && total == MAX_BLACKJACK {
+        let pays = &rules.blackjack_pays;
+        return (
+            Action::Stand,
+            pays.numerator as f64 / pays.denominator as f64,
+        );
+    }
+
+    let dealer = dealer_distribution(up_card, rules);
+    if lo_sum > MAX_BLACKJACK {
+        return (Action::Stand, -1.0);
+    }
+    let evs = play_on_evs(&dealer);
+    let mut best = (Action::Stand, stand_ev(total, &dealer));
+    let hit = hit_ev(lo_sum, has_ace, &evs);
+    if hit > best.1 {
+        best = (Action::Hit, hit);
+    }
+    if opening {
+        let double = double_ev(lo_sum, has_ace, &dealer);
+        if double > best.1 {
+            best = (Action::DoubleDown, double);
+        }
+        if (rules.late_surrender || rules.early_surrender) && -0.5 > best.1 {
+            best = (Action::Surrender, -0.5);
+        }
+    }
+    best
+}
+
+/// EV (in units of the original bet) of splitting a pair of `pair_rank` against the dealer's
+/// `up_card`. Each hand is dealt one more card and then hits or stands, whichever is better. Split
+/// Aces only get that one card. Doubling and re-splitting are not considered.
+pub fn split_ev(pair_rank: Rank, up_card: Rank, rules: &Rules) -> f64 {
+    let dealer = dealer_distribution(up_card, rules);
+    let evs = play_on_evs(&dealer);
+    let pair_value = low_value(pair_rank);
+    let is_ace = pair_rank == Rank::Ace;
+    let hand_ev: f64 = (1..=10)
+        .map(|value| {
+            let lo_sum = pair_value + value;
+            let has_ace = is_ace || value == 1;
+            // A split hand totaling 21 is not a natural
+            let ev = if is_ace {
+                stand_ev(best_total(lo_sum, has_ace), &dealer)
+            } else {
+                evs[lo_sum][has_ace as usize]
+            };
+            draw_probability(value) * ev
+        })
+        .sum();
+    // Two hands, each with the original bet
+    2.0 * hand_ev
+}
+
+/// EV (in units of the original bet) of taking `action` with the player's `cards` against the
+/// dealer's `up_card`, then playing on as well as possible. Splitting assumes the hand is a pair.
+pub fn action_ev(cards: &[Card], up_card: Rank, action: &Action, rules: &Rules) -> f64 {
+    let lo_sum: usize = cards.iter().map(|c| low_value(c.rank)).sum();
+    let has_ace = cards.iter().any(|c| c.rank == Rank::Ace);
+    if lo_sum > MAX_BLACKJACK {
+        return -1.0;
+    }
+    let dealer = dealer_distribution(up_card, rules);
+    match action {
+        Action::Stand => stand_ev(best_total(lo_sum, has_ace), &dealer),
+        Action::Hit => hit_ev(lo_sum, has_ace, &play_on_evs(&dealer)),
+        Action::DoubleDown => double_ev(lo_sum, has_ace, &dealer),
+        Action::Split => split_ev(cards[0].rank, up_card, rules),
+        Action::Surrender => -0.5,
+    }
+}
+
+/// Evaluates many (cards, dealer up card) spots in parallel. Results are in the same order as `spots`.
+pub fn evaluate_spots_parallel(spots: &[(Vec<Card>, Rank)], rules: &Rules) -> Vec<(Action, f64)> {
+    spots
+        .par_iter()
+        .map(|(cards, up_card)| evaluate_spot(cards, *up_card, rules))
+        .collect()
+}
+
+/// Builds a list of cards from ranks. Used in unit testing.
+#[cfg(test)]
+fn cards_of(ranks: &[Rank]) -> Vec<Card> {
+    ranks
+        .iter()
+        .map(|rank| Card {
+            suit: Suit::Spades,
+            rank: *rank,
+        })
+        .collect()
+}
+
+/// The dealer's results always sum to 1
+#[rstest]
+#[case(Rank::Two, false)]
+#[case(Rank::Six, true)]
+#[case(Rank::Ten, false)]
+#[case(Rank::Ace, true)]
+fn dealer_distribution_sums_to_one(#[case] up_card: Rank, #[case] dealer_hits_soft_17: bool) {
+    let rules = Rules {
+        dealer_hits_soft_17,
+        ..Rules::default()
+    };
+    let total: f64 = dealer_distribution(up_card, &rules).iter().sum();
+    assert!((total - 1.0).abs() < 1e-9)
+}
+
+/// Spot checks of well known basic strategy decisions
+#[rstest]
+#[case(vec![Rank::Six, Rank::Five], Rank::Six, Action::DoubleDown)]
+#[case(vec![Rank::Ten, Rank::Six], Rank::Ten, Action::Hit)]
+#[case(vec![Rank::Ten, Rank::Three], Rank::Four, Action::Stand)]
+#[case(vec![Rank::Ten, Rank::Eight], Rank::Ace, Action::Stand)]
+#[case(vec![Rank::Ace, Rank::Six], Rank::Four, Action::DoubleDown)]
+fn check_evaluate_spot(#[case] ranks: Vec<Rank>, #[case] up_card: Rank, #[case] expected: Action) {
+    let (action, _) = evaluate_spot(&cards_of(&ranks), up_card, &Rules::default());
+    assert_eq!(action, expected)
+}
+
+/// Hard 16 against a 10 is bad enough to surrender, when allowed
+#[rstest]
+fn surrender_sixteen_vs_ten() {
+    let rules = Rules {
+        late_surrender: true,
+        ..Rules::default()
+    };
+    let (action, ev) = evaluate_spot(&cards_of(&[Rank::Ten, Rank::Six]), Rank::Ten, &rules);
+    assert_eq!(action, Action::Surrender);
+    assert_eq!(ev, -0.5);
+}
+
+/// Parallel evaluation matches evaluating each spot one at a time
+#[rstest]
+fn parallel_matches_sequential() {
+    let rules = Rules::default();
+    let spots: Vec<(Vec<Card>, Rank)> = vec![
+        (cards_of(&[Rank::Ten, Rank::Two]), Rank::Three),
+        (cards_of(&[Rank::Nine, Rank::Two]), Rank::Ten),
+        (cards_of(&[Rank::Ace, Rank::Seven]), Rank::Nine),
+        (
+            cards_of(&[Rank::Five, Rank::Four, Rank::Three]),
+            Rank::Seven,
+        ),
+        (cards_of(&[Rank::Ace, Rank::King]), Rank::Six),
+    ];
+    let sequential: Vec<(Action, f64)> = spots
+        .iter()
+        .map(|(cards, up_card)| evaluate_spot(cards, *up_card, &rules))
+        .collect();
+    let parallel = evaluate_spots_parallel(&spots, &rules);
+    assert_eq!(parallel.len(), sequential.len());
+    for ((p_action, p_ev), (s_action, s_ev)) in parallel.iter().zip(sequential.iter()) {
+        assert_eq!(p_action, s_action);
+        assert_eq!(p_ev, s_ev);
+    }
+}
+
+/// Splitting 8s beats playing a hard 16 against a 6, but splitting 10s doesn't beat standing on 20
+#[rstest]
+#[case(Rank::Eight, Rank::Six, true)]
+#[case(Rank::Ten, Rank::Six, false)]
+#[case(Rank::Ace, Rank::Ten, true)]
+#[case(Rank::Five, Rank::Six, false)]
+fn check_split_ev(#[case] pair_rank: Rank, #[case] up_card: Rank, #[case] should_split: bool) {
+    let rules = Rules::default();
+    let (_, no_split_ev) = evaluate_spot(&cards_of(&[pair_rank, pair_rank]), up_card, &rules);
+    assert_eq!(
+        split_ev(pair_rank, up_card, &rules) > no_split_ev,
+        should_split
+    );
+}
+
+/// The EV of each action matches the EV of the best action
+#[rstest]
+fn check_action_ev() {
+    let rules = Rules::default();
+    let cards = cards_of(&[Rank::Ten, Rank::Six]);
+    let (best, best_ev) = evaluate_spot(&cards, Rank::Ten, &rules);
+    assert_eq!(action_ev(&cards, Rank::Ten, &best, &rules), best_ev);
+    assert!(action_ev(&cards, Rank::Ten, &Action::Stand, &rules) < best_ev);
+    assert_eq!(
+        action_ev(&cards, Rank::Ten, &Action::Surrender, &rules),
+        -0.5
+    );
+}
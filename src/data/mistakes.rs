@@ -0,0 +1,176 @@
+//!
+//! File:           mistakes.rs
+//! Description:    Audits a player's decisions against the probability table
+//!
+use rstest::rstest;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::data::ev::action_ev;
+use crate::data::probability_table::{get_action, get_pair_action, should_surrender_under, Action};
+#[cfg(test)]
+use crate::types::card::Suit;
+use crate::types::card::{Card, Rank, MAX_BLACKJACK};
+use crate::types::rules::Rules;
+
+/// A single decision made by a player
+#[derive(Clone)]
+pub struct Decision {
+    /// The player's cards when the decision was made
+    pub cards: Vec<Card>,
+    pub up_card: Rank,
+    pub action: Action,
+    /// The hand was made by splitting a pair, so it can't be surrendered
+    pub split: bool,
+}
+
+/// Returns the move the probability table player makes with `cards` under `rules`, assuming any pair
+/// can be split. Like the player, it only surrenders an opening hard hand that wasn't `split`.
+pub fn table_action(cards: &[Card], up_card: Rank, split: bool, rules: &Rules) -> Action {
+    if cards.len() == 2 && cards[0].rank.value() == cards[1].rank.value() {
+        if let Some(action) = get_pair_action(cards[0].rank, up_card, rules.das) {
+            return action;
+        }
+    }
+    let lo_sum: usize = cards
+        .iter()
+        .map(|c| match c.rank {
+            Rank::Ace => 1,
+            rank => rank.value(),
+        })
+        .sum();
+    let has_ace = cards.iter().any(|c| c.rank == Rank::Ace);
+    let soft = has_ace && lo_sum + 10 <= MAX_BLACKJACK;
+    let total = if soft { lo_sum + 10 } else { lo_sum };
+    if cards.len() == 2 && !split && !soft && should_surrender_under(total, up_card, rules) {
+        return Action::Surrender;
+    }
+    get_action(total, soft, up_card)
+}
+
+/// Counts and (approximate) EV cost of every decision that deviated from the probability table
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MistakeReport {
+    decisions: usize,
+    /// Keyed by "<action taken> instead of <table action>": number of deviations and total EV cost
+    deviations: BTreeMap<String, (usize, f64)>,
+}
+
+impl MistakeReport {
+    pub fn new() -> Self {
+        MistakeReport::default()
+    }
+
+    /// Audits a decision. The EV cost of a deviation is the EV given up (in units of the bet) versus
+    /// the table's move, assuming an infinite deck.
+    pub fn record(&mut self, decision: &Decision, rules: &Rules) {
+        self.decisions += 1;
+        let expected = table_action(&decision.cards, decision.up_card, decision.split, rules);
+        if expected == decision.action {
+            return;
+        }
+        let cost = action_ev(&decision.cards, decision.up_card, &expected, rules)
+            - action_ev(&decision.cards, decision.up_card, &decision.action, rules);
+        let entry = self
+            .deviations
+            .entry(format!("{} instead of {}", decision.action, expected))
+            .or_insert((0, 0.0));
+        entry.0 += 1;
+        entry.1 += cost;
+    }
+
+    /// Adds the decisions audited in another report
+    pub fn merge(&mut self, other: &MistakeReport) {
+        self.decisions += other.decisions;
+        for (kind, (count, cost)) in other.deviations.iter() {
+            let entry = self.deviations.entry(kind.clone()).or_insert((0, 0.0));
+            entry.0 += count;
+            entry.1 += cost;
+        }
+    }
+
+    /// Number of decisions audited
+    pub fn decisions(&self) -> usize {
+        self.decisions
+    }
+
+    /// Number of decisions that deviated from the probability table
+    pub fn total_deviations(&self) -> usize {
+        self.deviations.values().map(|(count, _)| count).sum()
+    }
+
+    /// Total EV (in units of the bet) given up by deviating from the probability table
+    pub fn total_ev_cost(&self) -> f64 {
+        self.deviations
+            .values()
+            .fold(0.0, |total, (_, cost)| total + cost)
+    }
+}
+
+impl fmt::Display for MistakeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Decisions: {} | Mistakes: {} | EV cost: {:.2} units",
+            self.decisions,
+            self.total_deviations(),
+            self.total_ev_cost(),
+        )
+        .expect("I/O Error");
+        for (kind, (count, cost)) in self.deviations.iter() {
+            writeln!(
+                f,
+                "  {}: {} times | EV cost: {:.3} units each",
+                kind,
+                count,
+                cost / *count as f64
+            )
+            .expect("I/O Error");
+        }
+        Ok(())
+    }
+}
+
+/// A table that always stands is caught making costly mistakes. Following the table costs nothing.
+#[rstest]
+fn suboptimal_table_reports_mistakes() {
+    let always_stand = |_: &[Card], _: Rank| Action::Stand;
+    let rules = Rules::default();
+    let mut report = MistakeReport::new();
+    let mut table_report = MistakeReport::new();
+    for first in [Rank::Five, Rank::Ten] {
+        for second in [Rank::Two, Rank::Six] {
+            for up_card in [Rank::Six, Rank::Ten] {
+                let cards = vec![
+                    Card {
+                        suit: Suit::Clubs,
+                        rank: first,
+                    },
+                    Card {
+                        suit: Suit::Hearts,
+                        rank: second,
+                    },
+                ];
+                let mut decision = Decision {
+                    action: always_stand(&cards, up_card),
+                    cards,
+                    up_card,
+                    split: false,
+                };
+                report.record(&decision, &rules);
+                decision.action = table_action(&decision.cards, decision.up_card, false, &rules);
+                table_report.record(&decision, &rules);
+            }
+        }
+    }
+    assert_eq!(report.decisions(), 8);
+    assert!(report.total_deviations() > 0);
+    assert!(report.total_ev_cost() > 0.0);
+    assert_eq!(table_report.total_deviations(), 0);
+
+    let mut merged = MistakeReport::new();
+    merged.merge(&report);
+    merged.merge(&table_report);
+    assert_eq!(merged.decisions(), 16);
+    assert_eq!(merged.total_deviations(), report.total_deviations());
+}
@@ -1 +1,6 @@
+pub mod analysis;
+pub mod chart;
+pub mod ev;
+pub mod mistakes;
 pub mod probability_table;
+pub mod simulation;
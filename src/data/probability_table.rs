@@ -1,42 +1,57 @@
-use crate::types::card::Rank;
+use rstest::rstest;
+use std::fmt;
+
+use crate::types::card::{Rank, MAX_BLACKJACK};
+use crate::types::rules::Rules;
 
 /// Player actions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Action {
     Hit,
     Stand,
     DoubleDown,
+    Split,
+    /// Forfeit half the bet and end the hand
+    Surrender,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Action::Hit => "Hit",
+            Action::Stand => "Stand",
+            Action::DoubleDown => "Double Down",
+            Action::Split => "Split",
+            Action::Surrender => "Surrender",
+        };
+        write!(f, "{}", name)
+    }
 }
 
-/// Determines which move an "optimized" player should make.
+/// Determines which move an "optimized" player should make. A `soft` hand counts an Ace as 11.
 /// Based on this strategy: https://m.media-amazon.com/images/I/816DFf5i0EL._SL1500_.jpg
-pub fn get_action(val: usize, up_card: Rank) -> Action {
+pub fn get_action(val: usize, soft: bool, up_card: Rank) -> Action {
+    if soft {
+        return get_soft_action(val, up_card);
+    }
+
     if val <= 8 {
         return Action::Hit;
     }
 
     if val == 9 {
-        return match up_card {
-            Rank::Two => Action::Hit,
-            Rank::Seven => Action::Hit,
-            Rank::Eight => Action::Hit,
-            Rank::Nine => Action::Hit,
-            Rank::Ten => Action::Hit,
-            Rank::Jack => Action::Hit,
-            Rank::Queen => Action::Hit,
-            Rank::King => Action::Hit,
-            Rank::Ace => Action::Hit,
-            _ => Action::DoubleDown,
+        return if (Rank::Three..=Rank::Six).contains(&up_card) {
+            Action::DoubleDown
+        } else {
+            Action::Hit
         };
     }
 
     if val == 10 {
-        return match up_card {
-            Rank::Ten => Action::Hit,
-            Rank::Jack => Action::Hit,
-            Rank::Queen => Action::Hit,
-            Rank::King => Action::Hit,
-            Rank::Ace => Action::Hit,
-            _ => Action::DoubleDown,
+        return if up_card.is_ten_value() || up_card.is_ace() {
+            Action::Hit
+        } else {
+            Action::DoubleDown
         };
     }
 
@@ -48,20 +63,337 @@ pub fn get_action(val: usize, up_card: Rank) -> Action {
         return Action::Stand;
     }
 
-    // Between [13, 16]
-    match up_card {
-        Rank::Two => Action::Stand,
-        Rank::Three => Action::Stand,
-        Rank::Four => Action::Stand,
-        Rank::Five => Action::Stand,
-        Rank::Six => Action::Stand,
-        Rank::Seven => Action::Hit,
-        Rank::Eight => Action::Hit,
-        Rank::Nine => Action::Hit,
-        Rank::Ten => Action::Hit,
-        Rank::Jack => Action::Hit,
-        Rank::Queen => Action::Hit,
-        Rank::King => Action::Hit,
-        Rank::Ace => Action::Hit,
+    // Between [12, 16]. A 12 only stands against the dealer's weakest cards, 4 through 6.
+    let weakest = if val == 12 { Rank::Four } else { Rank::Two };
+    if (weakest..=Rank::Six).contains(&up_card) {
+        Action::Stand
+    } else {
+        Action::Hit
+    }
+}
+
+/// Pair splitting rows of the strategy. Returns `Some(Action::Split)` if a pair of `rank` should be
+/// split against the dealer's `up_card`, otherwise `None` and the hand is played by its total. Encodes
+/// the multi-deck chart where the dealer stands on soft 17. Without doubling after a split (`das`),
+/// small pairs are split less often, since the split hands can't be doubled against a weak dealer.
+pub fn get_pair_action(rank: Rank, up_card: Rank, das: bool) -> Option<Action> {
+    let up = up_card.value();
+    let split = match rank.value() {
+        // Always split Aces and Eights
+        11 | 8 => true,
+        // Never split Fives or Tens
+        10 | 5 => false,
+        2 | 3 if !das => (4..=7).contains(&up),
+        2 | 3 | 7 => up <= 7,
+        4 => das && (5..=6).contains(&up),
+        6 if !das => (3..=6).contains(&up),
+        6 => up <= 6,
+        9 => up <= 9 && up != 7,
+        _ => false,
+    };
+    split.then_some(Action::Split)
+}
+
+/// Soft total rows of the strategy. Soft hands can't bust on one hit, so they are played more
+/// aggressively: doubling against a weak dealer, and hitting a soft 17 or a soft 18 against a strong
+/// one.
+fn get_soft_action(val: usize, up_card: Rank) -> Action {
+    let up = up_card.value();
+    match val {
+        13 | 14 if (5..=6).contains(&up) => Action::DoubleDown,
+        15 | 16 if (4..=6).contains(&up) => Action::DoubleDown,
+        17 | 18 if (3..=6).contains(&up) => Action::DoubleDown,
+        18 if up <= 8 => Action::Stand,
+        18 => Action::Hit,
+        v if v >= 19 => Action::Stand,
+        _ => Action::Hit,
     }
 }
+
+/// True count at or above which a card counter takes insurance
+pub const INSURANCE_TRUE_COUNT: f64 = 3.0;
+
+/// Determines which move a Hi-Lo card counter should make at the given `true_count`. Follows
+/// `get_action()`, except for the best known "index plays" (from the Illustrious 18), where the count
+/// changes the right move. Every index play is for a hard total.
+pub fn get_counting_action(val: usize, soft: bool, up_card: Rank, true_count: f64) -> Action {
+    if soft {
+        return get_action(val, soft, up_card);
+    }
+    let up = up_card.value();
+    let deviation = match (val, up) {
+        (16, 10) if true_count >= 0.0 => Some(Action::Stand),
+        (15, 10) if true_count >= 4.0 => Some(Action::Stand),
+        (12, 2) if true_count >= 3.0 => Some(Action::Stand),
+        (12, 3) if true_count >= 2.0 => Some(Action::Stand),
+        (12, 4) if true_count < 0.0 => Some(Action::Hit),
+        (13, 2) if true_count < -1.0 => Some(Action::Hit),
+        (10, 10) | (10, 11) if true_count >= 4.0 => Some(Action::DoubleDown),
+        (9, 2) if true_count >= 1.0 => Some(Action::DoubleDown),
+        (9, 7) if true_count >= 3.0 => Some(Action::DoubleDown),
+        _ => None,
+    };
+    deviation.unwrap_or_else(|| get_action(val, soft, up_card))
+}
+
+/// Determines if an "optimized" player should surrender their opening two cards (when the table
+/// allows it): hard 16 against a 9, 10 or Ace, and hard 15 against a 10.
+pub fn should_surrender(val: usize, up_card: Rank) -> bool {
+    match val {
+        16 => up_card.value() >= 9,
+        15 => up_card.is_ten_value(),
+        _ => false,
+    }
+}
+
+/// Returns true if basic strategy surrenders a hard `val` against `up_card` at a table with `rules`.
+/// Never true unless the table offers surrender. When the dealer hits a soft 17, a hard 15 and 17 are
+/// also given up against an Ace.
+pub fn should_surrender_under(val: usize, up_card: Rank, rules: &Rules) -> bool {
+    if !(rules.late_surrender || rules.early_surrender) {
+        return false;
+    }
+    should_surrender(val, up_card)
+        || (rules.dealer_hits_soft_17 && up_card.is_ace() && matches!(val, 15 | 17))
+}
+
+/// Returns the lowest hard total at which the probability table stands against `up_card` at a table
+/// with `rules`, i.e. 12 against a dealer 4 through 6, 13 against a 2 or 3, and 17 against a 7 or
+/// better. A surrendered total isn't stood on, so it's 18 against an Ace when the dealer hits a soft
+/// 17 and surrender is offered. Derived from `get_action()` and `should_surrender_under()`, so it
+/// can't drift from the moves the player makes.
+pub fn stand_threshold(up_card: Rank, rules: &Rules) -> usize {
+    let stands = |total| {
+        !should_surrender_under(total, up_card, rules)
+            && get_action(total, false, up_card) == Action::Stand
+    };
+    let mut threshold = MAX_BLACKJACK;
+    while threshold > 1 && stands(threshold - 1) {
+        threshold -= 1;
+    }
+    threshold
+}
+
+/// Explains, in plain English, why `action` is the right move for a `total` against the dealer's `up_card`.
+pub fn rationale(total: usize, up_card: Rank, action: &Action) -> String {
+    let dealer_is_weak = up_card.value() <= 6;
+    let reason = match action {
+        Action::DoubleDown => "double down because one more card is likely to make a strong hand",
+        Action::Split => "split because two hands starting from one card beat the pair's total",
+        Action::Surrender => "surrender because playing on loses more than half the bet on average",
+        Action::Hit if total <= 11 => "hit because you cannot bust",
+        Action::Hit if !dealer_is_weak => "hit because the dealer is strong",
+        Action::Hit => "hit because your total is too low to win by standing",
+        Action::Stand if total >= 17 => "stand because hitting is too likely to bust",
+        Action::Stand if dealer_is_weak => "stand because the dealer is weak and likely to bust",
+        Action::Stand => "stand because your total is strong enough",
+    };
+    format!("{} vs {}: {}", total, up_card, reason)
+}
+
+/// Validates that the teaching rationale references the chosen action
+#[rstest]
+#[case(11, Rank::Six, Action::DoubleDown, "11 vs 6: double down")]
+#[case(
+    16,
+    Rank::Ten,
+    Action::Hit,
+    "16 vs 10: hit because the dealer is strong"
+)]
+#[case(
+    13,
+    Rank::Four,
+    Action::Stand,
+    "13 vs 4: stand because the dealer is weak"
+)]
+#[case(18, Rank::Ace, Action::Stand, "18 vs Ace: stand")]
+fn check_rationale(
+    #[case] total: usize,
+    #[case] up_card: Rank,
+    #[case] action: Action,
+    #[case] expected: &str,
+) {
+    assert!(rationale(total, up_card, &action).starts_with(expected))
+}
+
+/// Validates the pair splitting rows
+#[rstest]
+#[case(Rank::Ace, Rank::Ace, true)]
+#[case(Rank::Eight, Rank::Ten, true)]
+#[case(Rank::Ten, Rank::Six, false)]
+#[case(Rank::King, Rank::Five, false)]
+#[case(Rank::Five, Rank::Six, false)]
+#[case(Rank::Two, Rank::Seven, true)]
+#[case(Rank::Three, Rank::Eight, false)]
+#[case(Rank::Four, Rank::Five, true)]
+#[case(Rank::Four, Rank::Four, false)]
+#[case(Rank::Six, Rank::Two, true)]
+#[case(Rank::Seven, Rank::Seven, true)]
+#[case(Rank::Nine, Rank::Seven, false)]
+#[case(Rank::Nine, Rank::Eight, true)]
+#[case(Rank::Nine, Rank::Ace, false)]
+fn check_pair_action(#[case] rank: Rank, #[case] up_card: Rank, #[case] expected: bool) {
+    assert_eq!(
+        get_pair_action(rank, up_card, true) == Some(Action::Split),
+        expected
+    );
+}
+
+/// Without doubling after a split, small pairs are split against fewer up cards
+#[rstest]
+#[case(Rank::Two, Rank::Two, true, false)]
+#[case(Rank::Three, Rank::Three, true, false)]
+#[case(Rank::Two, Rank::Four, true, true)]
+#[case(Rank::Four, Rank::Five, true, false)]
+#[case(Rank::Six, Rank::Two, true, false)]
+#[case(Rank::Six, Rank::Three, true, true)]
+#[case(Rank::Seven, Rank::Two, true, true)]
+#[case(Rank::Eight, Rank::Ace, true, true)]
+fn check_pair_action_das(
+    #[case] rank: Rank,
+    #[case] up_card: Rank,
+    #[case] with_das: bool,
+    #[case] without_das: bool,
+) {
+    assert_eq!(
+        get_pair_action(rank, up_card, true) == Some(Action::Split),
+        with_das
+    );
+    assert_eq!(
+        get_pair_action(rank, up_card, false) == Some(Action::Split),
+        without_das
+    );
+}
+
+/// Validates the hard total rows
+#[rstest]
+#[case(5, Rank::Six, Action::Hit)]
+#[case(8, Rank::Five, Action::Hit)]
+#[case(9, Rank::Two, Action::Hit)]
+#[case(9, Rank::Three, Action::DoubleDown)]
+#[case(9, Rank::Six, Action::DoubleDown)]
+#[case(9, Rank::Seven, Action::Hit)]
+#[case(10, Rank::Nine, Action::DoubleDown)]
+#[case(10, Rank::Ten, Action::Hit)]
+#[case(10, Rank::Ace, Action::Hit)]
+#[case(11, Rank::Two, Action::DoubleDown)]
+#[case(11, Rank::Ten, Action::DoubleDown)]
+#[case(11, Rank::Ace, Action::DoubleDown)]
+#[case(12, Rank::Two, Action::Hit)]
+#[case(12, Rank::Three, Action::Hit)]
+#[case(12, Rank::Four, Action::Stand)]
+#[case(12, Rank::Seven, Action::Hit)]
+#[case(13, Rank::Six, Action::Stand)]
+#[case(15, Rank::Queen, Action::Hit)]
+#[case(16, Rank::Five, Action::Stand)]
+#[case(16, Rank::Ten, Action::Hit)]
+#[case(16, Rank::Ace, Action::Hit)]
+#[case(17, Rank::Ten, Action::Stand)]
+#[case(17, Rank::Ace, Action::Stand)]
+#[case(20, Rank::Six, Action::Stand)]
+fn check_hard_action(#[case] val: usize, #[case] up_card: Rank, #[case] expected: Action) {
+    assert_eq!(get_action(val, false, up_card), expected);
+}
+
+/// Validates the soft total rows
+#[rstest]
+#[case(17, Rank::Seven, Action::Hit)]
+#[case(17, Rank::Four, Action::DoubleDown)]
+#[case(13, Rank::Five, Action::DoubleDown)]
+#[case(13, Rank::Four, Action::Hit)]
+#[case(16, Rank::Four, Action::DoubleDown)]
+#[case(18, Rank::Two, Action::Stand)]
+#[case(18, Rank::Eight, Action::Stand)]
+#[case(18, Rank::Nine, Action::Hit)]
+#[case(18, Rank::Ace, Action::Hit)]
+#[case(19, Rank::Six, Action::Stand)]
+fn check_soft_action(#[case] val: usize, #[case] up_card: Rank, #[case] expected: Action) {
+    assert_eq!(get_action(val, true, up_card), expected);
+}
+
+/// Validates which hands surrender
+#[rstest]
+#[case(16, Rank::Ten, true)]
+#[case(16, Rank::Ace, true)]
+#[case(15, Rank::King, true)]
+#[case(15, Rank::Nine, false)]
+#[case(16, Rank::Six, false)]
+#[case(17, Rank::Ten, false)]
+fn check_should_surrender(#[case] val: usize, #[case] up_card: Rank, #[case] expected: bool) {
+    assert_eq!(should_surrender(val, up_card), expected)
+}
+
+/// Card counters deviate from the table only when the count calls for it
+#[rstest]
+#[case(16, Rank::Ten, 1.0, Action::Stand)]
+#[case(16, Rank::Ten, -1.0, Action::Hit)]
+#[case(12, Rank::Two, 2.9, Action::Hit)]
+#[case(12, Rank::Two, 3.0, Action::Stand)]
+#[case(12, Rank::Three, 1.5, Action::Hit)]
+#[case(12, Rank::Three, 2.0, Action::Stand)]
+#[case(12, Rank::Three, 2.5, Action::Stand)]
+#[case(12, Rank::Four, -0.5, Action::Hit)]
+#[case(10, Rank::Ace, 4.0, Action::DoubleDown)]
+#[case(10, Rank::Ace, 3.0, Action::Hit)]
+#[case(17, Rank::Ten, -5.0, Action::Stand)]
+fn check_counting_action(
+    #[case] val: usize,
+    #[case] up_card: Rank,
+    #[case] true_count: f64,
+    #[case] expected: Action,
+) {
+    assert_eq!(
+        get_counting_action(val, false, up_card, true_count),
+        expected
+    )
+}
+
+/// Validates the stand thresholds against weak and strong dealer up cards, and how the rules move them
+#[rstest]
+#[case(Rank::Six, false, false, 12)]
+#[case(Rank::Four, false, false, 12)]
+#[case(Rank::Two, false, false, 13)]
+#[case(Rank::Three, false, false, 13)]
+#[case(Rank::Seven, false, false, 17)]
+#[case(Rank::Ten, false, false, 17)]
+#[case(Rank::Ace, false, false, 17)]
+#[case(Rank::Ace, true, false, 17)]
+#[case(Rank::Ace, false, true, 17)]
+#[case(Rank::Ace, true, true, 18)]
+#[case(Rank::Ten, true, true, 17)]
+fn check_stand_threshold(
+    #[case] up_card: Rank,
+    #[case] dealer_hits_soft_17: bool,
+    #[case] late_surrender: bool,
+    #[case] expected: usize,
+) {
+    let rules = Rules {
+        dealer_hits_soft_17,
+        late_surrender,
+        ..Rules::default()
+    };
+    assert_eq!(stand_threshold(up_card, &rules), expected)
+}
+
+/// Surrender follows the table rules: only when offered, and more often when the dealer hits a soft 17
+#[rstest]
+#[case(16, Rank::Ten, false, false, false)]
+#[case(16, Rank::Ten, false, true, true)]
+#[case(17, Rank::Ace, false, true, false)]
+#[case(17, Rank::Ace, true, true, true)]
+#[case(15, Rank::Ace, true, true, true)]
+#[case(17, Rank::Ace, true, false, false)]
+fn check_should_surrender_under(
+    #[case] val: usize,
+    #[case] up_card: Rank,
+    #[case] dealer_hits_soft_17: bool,
+    #[case] late_surrender: bool,
+    #[case] expected: bool,
+) {
+    let rules = Rules {
+        dealer_hits_soft_17,
+        late_surrender,
+        ..Rules::default()
+    };
+    assert_eq!(should_surrender_under(val, up_card, &rules), expected)
+}
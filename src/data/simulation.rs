@@ -0,0 +1,565 @@
+//!
+//! File:           simulation.rs
+//! Description:    Simulates sessions of automated players, in parallel
+//!
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::prelude::*;
+use rstest::rstest;
+use std::io;
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::types::betting::{BettingStrategy, Bettor, TableLimits, WalkAway};
+use crate::types::counting::HiLoCounter;
+use crate::types::deck::Deck;
+use crate::types::hand::{
+    Hand, Strategy, DEALER_INFINITE_CREDITS, DEFAULT_BET_VALUE, HUMAN_DEFAULT_CREDITS,
+    NO_BET_VALUE, UP_CARD_IDX,
+};
+use crate::types::round::{init_game, pay_out, play_insurance, play_player_hands, settle_hand};
+use crate::types::rules::Rules;
+#[cfg(test)]
+use crate::types::stats::SessionEnd;
+use crate::types::stats::{RunStats, TotalRunStats};
+
+/// Most games played in a run, unless configured otherwise
+pub const DEFAULT_MAX_GAMES_PER_RUN: usize = 50;
+/// Multi-seat tables and carried-over shoes deal from a shoe, so there are enough cards for every
+/// seat and for several rounds.
+pub const TABLE_SHOE_DECKS: usize = 6;
+/// Fraction of a carried-over shoe that is dealt before the cut card
+pub const DEFAULT_PENETRATION: f64 = 0.75;
+
+/// Settings shared by every run of a simulation
+#[derive(Clone)]
+pub struct SimConfig {
+    /// How the simulated player plays
+    pub strategy: Strategy,
+    /// Most rounds dealt in a single run. A split round records a game for each of its hands.
+    pub max_games: usize,
+    /// Explain each basic strategy decision
+    pub explain: bool,
+    pub rules: Rules,
+    /// Keep dealing from the same shoe between games, until the cut card is reached
+    pub carry_shoe: bool,
+    /// Fraction of a carried-over shoe dealt before the cut card
+    pub penetration: f64,
+    /// Audit the player's decisions
+    pub audit: bool,
+    /// How the player sizes their bets between games
+    pub betting: BettingStrategy,
+    /// Report the simulation's progress on stderr
+    pub progress: bool,
+    /// Credits the player sits down with
+    pub starting_credits: isize,
+    /// Bet before any betting strategy adjusts it
+    pub base_bet: isize,
+    /// Cards burned from every freshly shuffled deck
+    pub burn: usize,
+    /// Smallest and largest bets the table takes
+    pub limits: TableLimits,
+    /// Pushes end win and loss streaks, instead of being skipped over
+    pub pushes_end_streaks: bool,
+    /// When the player leaves before playing every game
+    pub walk_away: WalkAway,
+}
+
+impl Default for SimConfig {
+    /// A basic strategy player, flat betting with a fresh deck every game under the standard rules
+    fn default() -> Self {
+        SimConfig {
+            strategy: Strategy::ProbabilityTable,
+            max_games: DEFAULT_MAX_GAMES_PER_RUN,
+            explain: false,
+            rules: Rules::default(),
+            carry_shoe: false,
+            penetration: DEFAULT_PENETRATION,
+            audit: false,
+            betting: BettingStrategy::Flat,
+            progress: false,
+            starting_credits: HUMAN_DEFAULT_CREDITS,
+            base_bet: DEFAULT_BET_VALUE,
+            burn: 0,
+            limits: TableLimits::default(),
+            pushes_end_streaks: false,
+            walk_away: WalkAway::default(),
+        }
+    }
+}
+
+impl SimConfig {
+    /// Builds a fresh deck: the shoe the rules call for, if any. Otherwise a shoe when it is carried
+    /// between games, or a single deck. Cards are burned from it before it's dealt.
+    pub fn new_deck(&self, rng: &mut StdRng) -> Deck {
+        let num_decks = match self.rules.decks {
+            Some(decks) => decks,
+            None if self.carry_shoe => TABLE_SHOE_DECKS,
+            None => 1,
+        };
+        let mut deck = Deck::with_rng(num_decks, rng);
+        deck.burn(self.burn);
+        deck
+    }
+
+    /// Returns every card to `deck`, reshuffles and burns it, like `new_deck()` but reusing the
+    /// allocation.
+    pub fn reset_deck(&self, deck: &mut Deck, rng: &mut StdRng) {
+        deck.reset_with(rng);
+        deck.burn(self.burn);
+    }
+
+    /// Returns true if `deck` should be replaced before the next game.
+    pub fn needs_new_deck(&self, deck: &Deck) -> bool {
+        // According to the internet, digital Blackjack machines reset the deck every game instance.
+        !self.carry_shoe || deck.needs_reshuffle(self.penetration)
+    }
+}
+
+/// Plays a game with the dealer at most `max_games` number of times. Bails early if the player runs out of money.
+/// This simulates a single "session" of a player sitting down to play a game. Every deck is shuffled
+/// from `seed`, so a run is reproducible. When the shoe is carried, it is only reshuffled at the cut
+/// card.
+pub fn run_automated_match(config: &SimConfig, seed: u64) -> RunStats {
+    let rules = config.rules;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut deck = config.new_deck(&mut rng);
+    let mut dealer = Hand::new("Dealer", Strategy::Dealer, DEALER_INFINITE_CREDITS);
+    dealer.set_rules(rules);
+    let mut player = Hand::new(
+        "Auto Player",
+        config.strategy.clone(),
+        config.starting_credits,
+    );
+    player.set_explain(config.explain);
+    player.set_rules(rules);
+    player.set_audit(config.audit);
+    // The count carries over between games dealt from the same shoe
+    let mut counter = HiLoCounter::new();
+    // Betting progressions start over every run
+    let mut bettor = Bettor::new(config.betting, config.base_bet);
+
+    let mut stats = RunStats::new();
+    stats.set_pushes_end_streaks(config.pushes_end_streaks);
+    stats.record_sequence_length(bettor.sequence_len());
+    stats.record_credit_snapshot(player.get_credits());
+
+    for _ in 0..config.max_games {
+        // Card counters size their bet by the count before the deal
+        // The table's limits apply first, then the credits available
+        let wanted = match config.strategy {
+            Strategy::CardCounter => counter.bet(&deck, config.base_bet, isize::MAX),
+            _ => bettor.wanted_bet(),
+        };
+        let (limited, at_table_max) = config.limits.clamp(wanted);
+        if at_table_max {
+            stats.record_table_max_cap();
+        }
+        if limited > player.get_credits() && config.strategy != Strategy::CardCounter {
+            stats.record_capped_bet();
+        }
+        let bet = limited.min(player.get_credits());
+        stats.record_bet(bet);
+        let credits_before = player.get_credits();
+        init_game(slice::from_mut(&mut player), &mut dealer, &mut deck);
+        player.sub_credits(bet);
+
+        // Only the player's cards and the dealer's up card can be seen
+        let mut visible = player.get_cards().to_vec();
+        visible.push(dealer.get_cards()[UP_CARD_IDX]);
+        player.set_true_count(counter.true_count(&deck, &visible));
+
+        // Early surrender comes before the dealer peeks, so it also saves half the bet from a natural
+        let up_card = dealer.get_up_card_rank();
+        let surrendered_early = player.offer_early_surrender(up_card, &mut io::empty());
+
+        // Even money is offered instead of insurance on a natural
+        let even_money = player.offer_even_money(up_card, &mut io::empty());
+        if let Some(taken) = even_money {
+            stats.record_even_money(taken);
+        }
+        let (side_bet, returned) = match even_money {
+            Some(_) => (0, 0),
+            None if surrendered_early => (0, 0),
+            None => play_insurance(&mut player, &dealer, bet, &mut io::empty()),
+        };
+        if side_bet > 0 {
+            stats.record_insurance(side_bet, returned);
+        }
+        let insurance_net = returned - side_bet;
+
+        // Naturals are resolved right after the deal, so nobody acts. Without the peek, a dealer
+        // natural isn't known until the player has acted.
+        let resolved_at_deal = surrendered_early
+            || (rules.dealer_peeks && dealer.peek_for_blackjack())
+            || player.is_natural();
+
+        // Player control
+        let mut hands = if resolved_at_deal {
+            // Decisions (i.e. an early surrender) move to the settled hand, so they're audited once
+            let hand = player.clone();
+            player.take_decisions();
+            vec![(hand, bet)]
+        } else {
+            play_player_hands(
+                &mut player,
+                &mut deck,
+                bet,
+                up_card,
+                &mut io::empty(),
+                |_| (),
+            )
+        };
+
+        // Dealer control
+        if !resolved_at_deal {
+            loop {
+                let (stop, _) =
+                    dealer.play_once(&mut deck, NO_BET_VALUE, dealer.get_up_card_rank());
+                if stop {
+                    break;
+                }
+            }
+            stats.record_dealer_hand(dealer.final_value());
+        }
+
+        let mut outcomes = Vec::with_capacity(hands.len());
+        for (hand, final_bet) in hands.iter_mut() {
+            let (match_outcome, final_bet) =
+                settle_hand(hand, &dealer, *final_bet, even_money == Some(true));
+            pay_out(&mut player, &match_outcome, final_bet);
+            outcomes.push(match_outcome);
+            stats.record_decisions(&hand.take_decisions(), &rules);
+        }
+        match outcomes.as_slice() {
+            [outcome] => stats.record_match_end(*outcome),
+            split => stats.record_split_result(split),
+        }
+        stats.record_credit_snapshot(player.get_credits());
+        // Insurance is a side bet, so it doesn't decide if the round was won
+        bettor.record_round(player.get_credits() - credits_before - insurance_net);
+        stats.record_sequence_length(bettor.sequence_len());
+
+        // Broke players can't play
+        if player.get_credits() <= 0 {
+            stats.record_bust();
+            break;
+        }
+        if let Some(session_end) = config.walk_away.check(player.get_credits()) {
+            stats.record_session_end(session_end);
+            break;
+        }
+
+        player.clear_hand();
+        dealer.clear_hand();
+        counter.count_dealt(&deck);
+        if config.needs_new_deck(&deck) {
+            config.reset_deck(&mut deck, &mut rng);
+            counter.reset();
+        }
+    }
+
+    stats.record_credits(player.get_credits());
+    stats
+}
+
+/// Runs a simulation in parallel, playing one run per seed in `run_seeds`, and aggregates the results
+/// of every run.
+pub fn simulate(config: &SimConfig, run_seeds: &[u64]) -> TotalRunStats {
+    total_of(&simulate_runs(config, run_seeds), config.starting_credits)
+}
+
+/// Counts finished runs from every thread, reporting the percentage done on stderr
+struct Progress {
+    done: AtomicUsize,
+    total: usize,
+}
+
+impl Progress {
+    fn new(total: usize) -> Self {
+        Progress {
+            done: AtomicUsize::new(0),
+            total,
+        }
+    }
+
+    /// Records a finished run. Only prints when the percentage changes.
+    fn tick(&self) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        let percent = 100 * done / self.total;
+        if percent != 100 * (done - 1) / self.total {
+            eprint!("\rProgress: {:>3}%", percent);
+        }
+        if done == self.total {
+            eprintln!();
+        }
+    }
+}
+
+/// Plays every run of a simulation, returning each run's results
+pub fn simulate_runs(config: &SimConfig, run_seeds: &[u64]) -> Vec<RunStats> {
+    let progress = Progress::new(run_seeds.len());
+    // Each game is run in a parallel using rayon's `map()` functionality.
+    run_seeds
+        .par_iter()
+        .map(|seed| {
+            let stats = run_automated_match(config, *seed);
+            if config.progress {
+                progress.tick();
+            }
+            stats
+        })
+        .collect()
+}
+
+/// Aggregates the results of every run, where players started with `starting_credits`
+pub fn total_of(runs: &[RunStats], starting_credits: isize) -> TotalRunStats {
+    let mut total_stats = TotalRunStats::new(starting_credits);
+    for stats in runs {
+        total_stats.add_run(stats);
+    }
+    total_stats
+}
+
+/// Every seed plays one run, and the results are aggregated
+#[rstest]
+fn simulate_every_seed() {
+    let total = simulate(&SimConfig::default(), &[1, 2, 3]);
+    assert_eq!(total.num_runs(), 3);
+    assert!(total.num_games() > 0);
+}
+
+/// The same seed replays the same simulation
+#[rstest]
+#[case(false)]
+#[case(true)]
+fn seeded_runs_are_reproducible(#[case] carry_shoe: bool) {
+    let config = SimConfig {
+        carry_shoe,
+        ..SimConfig::default()
+    };
+    assert_eq!(
+        run_automated_match(&config, 1234),
+        run_automated_match(&config, 1234)
+    );
+}
+
+/// A carried-over shoe is only replaced at the cut card. Otherwise, every game gets a fresh deck.
+#[rstest]
+fn carried_shoe_replaced_at_cut_card() {
+    let mut config = SimConfig {
+        carry_shoe: true,
+        penetration: 0.5,
+        ..SimConfig::default()
+    };
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut shoe = config.new_deck(&mut rng);
+    assert_eq!(shoe.total(), TABLE_SHOE_DECKS * 52);
+    // A few games' worth of cards
+    for _ in 0..20 {
+        shoe.deal();
+    }
+    assert!(!config.needs_new_deck(&shoe));
+    while shoe.remaining() > shoe.total() / 2 {
+        shoe.deal();
+    }
+    assert!(config.needs_new_deck(&shoe));
+
+    config.carry_shoe = false;
+    assert!(config.needs_new_deck(&config.new_deck(&mut rng)));
+}
+
+/// Presets are simulated with the shoe their published house edges assume, carried over or not
+#[rstest]
+#[case("vegas-strip", false, 4)]
+#[case("atlantic-city", true, 8)]
+#[case("european", false, 6)]
+fn preset_sets_shoe_size(#[case] preset: &str, #[case] carry_shoe: bool, #[case] decks: usize) {
+    let config = SimConfig {
+        rules: Rules::preset(preset).unwrap(),
+        carry_shoe,
+        ..SimConfig::default()
+    };
+    let mut rng = StdRng::seed_from_u64(1);
+    assert_eq!(config.new_deck(&mut rng).num_decks(), decks);
+}
+
+/// A card counter plays through a carried-over shoe, betting at least the base bet every game
+#[rstest]
+fn card_counter_run() {
+    let config = SimConfig {
+        strategy: Strategy::CardCounter,
+        carry_shoe: true,
+        ..SimConfig::default()
+    };
+    let stats = run_automated_match(&config, 99);
+    assert!(!stats.net_units().is_empty());
+    assert_eq!(stats, run_automated_match(&config, 99));
+}
+
+/// Basic strategy audits clean against its own table, surrenders included
+#[rstest]
+fn probability_table_audits_clean_with_surrender() {
+    let config = SimConfig {
+        audit: true,
+        rules: Rules {
+            late_surrender: true,
+            ..Rules::default()
+        },
+        ..SimConfig::default()
+    };
+    let runs = simulate_runs(&config, &(0..50).collect::<Vec<u64>>());
+    assert!(runs.iter().map(|run| run.surrenders()).sum::<usize>() > 0);
+    for run in runs.iter() {
+        assert!(run.mistakes().decisions() > 0);
+        assert_eq!(run.mistakes().total_deviations(), 0);
+    }
+}
+
+/// Early surrender is audited once per round, and never gives up a pair basic strategy splits
+#[rstest]
+fn early_surrender_audits_clean() {
+    let config = SimConfig {
+        audit: true,
+        rules: Rules {
+            early_surrender: true,
+            ..Rules::default()
+        },
+        ..SimConfig::default()
+    };
+    let runs = simulate_runs(&config, &(0..50).collect::<Vec<u64>>());
+    assert!(runs.iter().map(|run| run.surrenders()).sum::<usize>() > 0);
+    for run in runs.iter() {
+        assert_eq!(run.mistakes().total_deviations(), 0);
+        // At most a handful of decisions a game, not a growing backlog of repeats
+        assert!(run.mistakes().decisions() < 4 * run.num_games());
+    }
+}
+
+/// A Martingale bettor's doubled bets are capped to the credits left
+#[rstest]
+fn martingale_run() {
+    let config = SimConfig {
+        betting: BettingStrategy::Martingale,
+        ..SimConfig::default()
+    };
+    let runs = simulate_runs(&config, &(0..50).collect::<Vec<u64>>());
+    assert!(runs.iter().all(|run| run.remaining_credits() >= 0));
+    assert!(total_of(&runs, HUMAN_DEFAULT_CREDITS)
+        .to_string()
+        .contains("Bets capped by credits"));
+}
+
+/// A Fibonacci bettor's bets escalate past the base bet, and a flat bettor's never do
+#[rstest]
+#[case(BettingStrategy::Fibonacci, true)]
+#[case(BettingStrategy::Flat, false)]
+fn fibonacci_run(#[case] betting: BettingStrategy, #[case] escalates: bool) {
+    let config = SimConfig {
+        betting,
+        ..SimConfig::default()
+    };
+    let runs = simulate_runs(&config, &(0..20).collect::<Vec<u64>>());
+    let max_bet = runs.iter().map(|run| run.max_bet()).max().unwrap();
+    assert_eq!(max_bet > DEFAULT_BET_VALUE, escalates);
+}
+
+/// A player with a close target and stop-loss walks away before playing every game, with their credits
+/// at one or the other
+#[rstest]
+fn walk_away_run() {
+    let config = SimConfig {
+        max_games: 1000,
+        walk_away: WalkAway {
+            target: Some(HUMAN_DEFAULT_CREDITS + 5),
+            stop_loss: Some(HUMAN_DEFAULT_CREDITS - 5),
+        },
+        ..SimConfig::default()
+    };
+    for run in simulate_runs(&config, &(0..20).collect::<Vec<u64>>()) {
+        match run.session_end() {
+            SessionEnd::Target => assert!(run.remaining_credits() >= HUMAN_DEFAULT_CREDITS + 5),
+            SessionEnd::StopLoss => assert!(run.remaining_credits() <= HUMAN_DEFAULT_CREDITS - 5),
+            other => panic!("Session should have ended at a walk-away rule: {:?}", other),
+        }
+        assert!(run.num_games() < 1000);
+    }
+}
+
+/// The dealer busts about 28% of the time, a bit more when hitting soft 17
+#[rstest]
+fn dealer_bust_rate_run() {
+    let config = SimConfig::default();
+    let bust_rate = simulate(&config, &(0..200).collect::<Vec<u64>>()).dealer_bust_rate();
+    assert!((0.24..0.32).contains(&bust_rate), "{}", bust_rate);
+}
+
+/// A Monte Carlo bettor's sequence grows past its starting length over a run
+#[rstest]
+fn monte_carlo_run() {
+    let config = SimConfig {
+        betting: BettingStrategy::MonteCarlo,
+        ..SimConfig::default()
+    };
+    let runs = simulate_runs(&config, &(0..10).collect::<Vec<u64>>());
+    assert!(runs.iter().all(|run| run.longest_sequence() >= 3));
+    assert!(runs.iter().any(|run| run.longest_sequence() > 3));
+}
+
+/// Progress is counted correctly when runs finish on several threads
+#[rstest]
+fn progress_counts_every_run() {
+    let progress = Progress::new(1000);
+    (0..1000).into_par_iter().for_each(|_| progress.tick());
+    assert_eq!(progress.done.load(Ordering::Relaxed), 1000);
+}
+
+/// A table maximum stops a Martingale bettor's doubling
+#[rstest]
+fn table_max_caps_martingale() {
+    let config = SimConfig {
+        betting: BettingStrategy::Martingale,
+        limits: TableLimits::new(1, Some(4)),
+        ..SimConfig::default()
+    };
+    let runs = simulate_runs(&config, &(0..20).collect::<Vec<u64>>());
+    assert!(runs.iter().all(|run| run.max_bet() <= 4));
+    assert!(runs.iter().any(|run| run.table_max_caps() > 0));
+    assert!(total_of(&runs, HUMAN_DEFAULT_CREDITS)
+        .to_string()
+        .contains("Bets capped by the table max"));
+}
+
+/// Simulated players start with the configured credits and bet
+#[rstest]
+fn custom_bankroll_run() {
+    let config = SimConfig {
+        max_games: 1,
+        starting_credits: 1000,
+        base_bet: 10,
+        ..SimConfig::default()
+    };
+    let runs = simulate_runs(&config, &(0..20).collect::<Vec<u64>>());
+    // Every game is settled in multiples of half the $10 bet
+    assert!(runs
+        .iter()
+        .all(|run| (run.remaining_credits() - 1000) % 5 == 0));
+    assert!(runs
+        .iter()
+        .any(|run| (run.remaining_credits() - 1000).abs() >= 10));
+}
+
+/// A session stops after the configured number of hands
+#[rstest]
+fn session_length() {
+    let config = SimConfig {
+        max_games: 7,
+        ..SimConfig::default()
+    };
+    // Every hand split off counts as a game of its own
+    let most_games = 7 * (Rules::default().max_splits + 1);
+    for run in simulate_runs(&config, &[1, 2, 3]) {
+        assert!((7..=most_games).contains(&run.num_games()));
+    }
+}
@@ -0,0 +1,12 @@
+//!
+//! File:           lib.rs
+//! Description:    BlackJack game engine and simulator, usable as a library
+//!
+pub mod data;
+pub mod types;
+
+pub use data::simulation::{simulate, SimConfig};
+pub use types::card::{Card, Rank, Suit};
+pub use types::deck::{Deck, DeckEmpty};
+pub use types::hand::{Hand, Outcome, Strategy};
+pub use types::stats::{RunStats, TotalRunStats};
@@ -2,23 +2,64 @@
 //! File:           main.rs
 //! Description:    CLI interface for this project
 //!
-use std::io::{self, Write};
-use std::{process, thread, time};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::slice;
+use std::sync::Mutex;
+use std::{fs, process, thread, time};
 
-use clap::Parser;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, ValueEnum};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng, SeedableRng};
 use rayon::prelude::*;
+use rstest::rstest;
 
-use crate::types::deck::Deck;
-use crate::types::hand::{
+use rust_blackjack::data::chart::{
+    action_cell, chart_up_cards, strategy_chart_html, CHART_MAX_TOTAL, CHART_MIN_TOTAL,
+};
+use rust_blackjack::data::probability_table::{get_action, Action};
+#[cfg(feature = "json")]
+use rust_blackjack::data::simulation::run_automated_match;
+use rust_blackjack::data::simulation::{
+    simulate, simulate_runs, total_of, SimConfig, DEFAULT_MAX_GAMES_PER_RUN, DEFAULT_PENETRATION,
+    TABLE_SHOE_DECKS,
+};
+use rust_blackjack::types::betting::{BettingStrategy, TableLimits, WalkAway};
+use rust_blackjack::types::card::{Card, CardStyle, Rank, Suit, MAX_BLACKJACK};
+use rust_blackjack::types::deck::Deck;
+#[cfg(test)]
+use rust_blackjack::types::hand::UP_CARD_IDX;
+use rust_blackjack::types::hand::{
     Hand, Outcome, Strategy, DEALER_INFINITE_CREDITS, DEFAULT_BET_VALUE, HUMAN_DEFAULT_CREDITS,
     NO_BET_VALUE,
 };
-use crate::types::stats::{RunStats, TotalRunStats};
+use rust_blackjack::types::messages::{MessageKey, Messages};
+use rust_blackjack::types::round::{
+    init_game, pay_out, play_insurance, play_player_hands, settle_hand,
+};
+use rust_blackjack::types::rules::{break_even_win_rate, Payout, Rules, RULES_PRESETS};
+use rust_blackjack::types::stats::{CreditHistogram, RunStats};
 
-pub mod data;
-pub mod types;
+/// Hands simulated for every move in every cell of the EV table, unless RUNS is given
+const DEFAULT_EV_TABLE_TRIALS: usize = 10_000;
+/// Pause between the steps of the dealer's turn, in milliseconds
+const DEFAULT_DELAY_MS: u64 = 1000;
+/// Lowest total the dealer may be configured to stand on
+const MIN_DEALER_STANDS: usize = 12;
+/// Credits of the other seats at a simulated table: enough to always double down and split, like a
+/// real basic strategy player, without ever running out
+const OTHER_SEAT_CREDITS: isize = isize::MAX / 2;
 
-const DEFAULT_MAX_GAMES_PER_RUN: usize = 50;
+/// How simulation results are printed
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable text
+    Text,
+    /// A JSON object (requires the `json` feature)
+    Json,
+}
 
 #[derive(Parser)]
 #[command(
@@ -30,14 +71,309 @@ struct CliArgs {
     /// Number of simulations to run. A negative value will start a human-playable game.
     #[arg(default_value_t=-1)]
     runs: isize,
+    /// Teaching mode: explain each basic strategy decision (shown as hints in the human game).
+    #[arg(long)]
+    explain: bool,
+    /// Card counting practice: adds a command to the human game that shows what's left in the deck.
+    #[arg(long)]
+    practice: bool,
+    /// Let the human player decide what to do on 21, instead of standing automatically.
+    #[arg(long)]
+    no_auto_stand: bool,
+    /// Write the basic strategy chart to an HTML file and exit.
+    #[arg(long, value_name = "PATH")]
+    chart_html: Option<PathBuf>,
+    /// Number of seats at a simulated table. With more than 1 seat, compares the first seat's
+    /// results when the other seats play basic strategy versus randomly.
+    #[arg(long, default_value_t = 1)]
+    seats: usize,
+    /// Start from a common casino ruleset: vegas-strip, atlantic-city or european, dealt from the
+    /// preset's shoe. The other rule options override it. The README lists what each preset sets.
+    #[arg(long, value_name = "PRESET", value_parser = parse_rules_preset)]
+    rules: Option<Rules>,
+    /// Allow surrendering half the bet on the opening two cards, after the dealer checks for BlackJack.
+    #[arg(long, overrides_with = "no_late_surrender")]
+    late_surrender: bool,
+    /// Don't allow late surrender.
+    #[arg(long, overrides_with = "late_surrender")]
+    no_late_surrender: bool,
+    /// Allow surrendering half the bet on the opening two cards, before the dealer checks for
+    /// BlackJack, so it also saves half the bet from a dealer natural.
+    #[arg(long, overrides_with = "no_early_surrender")]
+    early_surrender: bool,
+    /// Don't allow early surrender.
+    #[arg(long, overrides_with = "early_surrender")]
+    no_early_surrender: bool,
+    /// The dealer hits a soft 17 (an Ace counted as 11), instead of standing on all 17s.
+    #[arg(long, overrides_with = "dealer_stands_soft_17")]
+    dealer_hits_soft_17: bool,
+    /// The dealer stands on all 17s, including a soft 17.
+    #[arg(long, overrides_with = "dealer_hits_soft_17")]
+    dealer_stands_soft_17: bool,
+    /// Total the dealer stands on, from 12 to 21 (17 by default).
+    #[arg(long, value_name = "N", value_parser = parse_dealer_stands)]
+    dealer_stands: Option<usize>,
+    /// US rules: the dealer peeks for BlackJack, so a dealer natural only takes the original bet.
+    #[arg(long, overrides_with = "no_peek")]
+    peek: bool,
+    /// European rules: the dealer doesn't peek for BlackJack, so a dealer natural also takes the extra
+    /// wagers from doubles and splits.
+    #[arg(long, overrides_with = "peek")]
+    no_peek: bool,
+    /// Payout ratio of a winning natural, i.e. 3:2 or 6:5 (3:2 by default).
+    #[arg(long, value_name = "RATIO")]
+    blackjack_pays: Option<Payout>,
+    /// Doubling down is allowed on the hands made by splitting a pair.
+    #[arg(long, overrides_with = "no_das")]
+    das: bool,
+    /// Doubling down isn't allowed on the hands made by splitting a pair.
+    #[arg(long, overrides_with = "das")]
+    no_das: bool,
+    /// Estimate the EV of hitting, standing and doubling down on every hard total against every dealer
+    /// up card by simulation, print it as a grid and exit. RUNS sets the number of hands simulated for
+    /// every move (10000 by default).
+    #[arg(long)]
+    ev_table: bool,
+    /// Pause between the steps of the dealer's turn in the human game, in milliseconds. 0 doesn't pause.
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_DELAY_MS)]
+    delay_ms: u64,
+    /// Show cards in the human game with colored suit symbols.
+    #[arg(long)]
+    color: bool,
+    /// Simulations keep dealing from a 6 deck shoe (or the preset's shoe) across the games of a run,
+    /// until the cut card is reached. By default, a fresh deck is used every game.
+    #[arg(long)]
+    carry_shoe: bool,
+    /// Replay a scenario in the human game: deal the cards listed in a file (i.e. "AS KH 7D ..."), in
+    /// order, instead of shuffled decks. Once the listed cards run out, play continues with shuffled decks.
+    #[arg(long, value_name = "PATH")]
+    scenario: Option<PathBuf>,
+    /// Number of cards burned (discarded unseen) from every freshly shuffled deck, before it's dealt.
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    burn: usize,
+    /// Fraction of a carried-over shoe that is dealt before it is reshuffled.
+    #[arg(long, value_name = "FRACTION", default_value_t = DEFAULT_PENETRATION, value_parser = parse_penetration)]
+    penetration: f64,
+    /// Audit every simulated decision against the probability table, reporting the mistakes made and
+    /// the expected value they cost.
+    #[arg(long)]
+    mistakes: bool,
+    /// Compare a Hi-Lo card counter against basic strategy. Both deal from carried-over shoes.
+    #[arg(long)]
+    count_cards: bool,
+    /// Stream the results of each simulated run as a line of JSON (NDJSON), as soon as it finishes.
+    #[arg(long)]
+    ndjson: bool,
+    /// A push ends a win or loss streak in the simulation results. By default, pushes are skipped over.
+    #[arg(long)]
+    pushes_end_streaks: bool,
+    /// Also print a histogram of the credits players walked away with, in bins WIDTH credits wide (25
+    /// by default).
+    #[arg(long, value_name = "WIDTH", num_args = 0..=1, default_missing_value = "25")]
+    histogram: Option<usize>,
+    /// Only print the simulation's total results, for scripts and pipes. Turns off --explain and
+    /// --progress. The human game needs its prompts, so it can't be quiet.
+    #[arg(long)]
+    quiet: bool,
+    /// How simulation results are printed.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+    /// With JSON output, also include the results of every run.
+    #[arg(long)]
+    verbose: bool,
+    /// Also write the results of every simulated run to a CSV file, one row per run.
+    #[arg(long, value_name = "PATH")]
+    csv: Option<PathBuf>,
+    /// How simulated players size their bets between games: flat, martingale (double after a loss),
+    /// paroli (double after a win), monte-carlo (bet from a cancellation sequence) or fibonacci (next
+    /// Fibonacci number after a loss, back two after a win).
+    #[arg(long, value_name = "STRATEGY", default_value = "flat")]
+    betting: BettingStrategy,
+    /// Run the simulation once per betting strategy, from the same seeds, and compare their results
+    /// side by side.
+    #[arg(long)]
+    compare_betting: bool,
+    /// Number of human players seated at the interactive game. Each player bets and plays in turn.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    players: u16,
+    /// Save each human player's credits to this file when they cash out.
+    #[arg(long, value_name = "PATH")]
+    save: Option<PathBuf>,
+    /// Start the human players with the credits saved in this file. Falls back to the default credits
+    /// if the file is missing.
+    #[arg(long, value_name = "PATH")]
+    load: Option<PathBuf>,
+    /// Show the progress of a simulation (on stderr), for long simulations.
+    #[arg(long)]
+    progress: bool,
+    /// Smallest bet the table takes.
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    table_min: isize,
+    /// Largest bet the table takes, which stops progressive betting strategies. Unlimited by default.
+    #[arg(long, value_name = "N")]
+    table_max: Option<isize>,
+    /// Simulated players walk away once their credits reach N.
+    #[arg(long, value_name = "N")]
+    target: Option<isize>,
+    /// Simulated players walk away once their credits fall to N.
+    #[arg(long, value_name = "N")]
+    stop_loss: Option<isize>,
+    /// Credits every player starts with.
+    #[arg(long, default_value_t = HUMAN_DEFAULT_CREDITS)]
+    credits: isize,
+    /// Starting bet. Simulated players bet this every game (before a betting strategy adjusts it).
+    #[arg(long, default_value_t = DEFAULT_BET_VALUE)]
+    bet: isize,
+    /// Number of hands played in a session: by each simulated run (50 by default), or before the
+    /// human game ends (unlimited by default). A session ends early if the player runs out of credits.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    hands: Option<u32>,
+    /// Seed for reproducible simulations. Each run derives its own seed from this one.
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+/// Validates a shoe penetration, which must be a fraction between 0 and 1.
+fn parse_penetration(arg: &str) -> Result<f64, String> {
+    let penetration: f64 = arg
+        .parse()
+        .map_err(|_| format!("`{}` isn't a number", arg))?;
+    if penetration <= 0.0 || penetration >= 1.0 {
+        return Err(String::from("penetration must be between 0 and 1"));
+    }
+    Ok(penetration)
+}
+
+/// Resolves a pair of on/off flags, keeping `current` when neither was given. Only the last of the
+/// pair given on the command line is set.
+fn flag_pair(on: bool, off: bool, current: bool) -> bool {
+    match (on, off) {
+        (true, _) => true,
+        (_, true) => false,
+        _ => current,
+    }
+}
+
+/// Builds the table rules from the preset (if any), overridden by the individual rule options.
+fn table_rules(args: &CliArgs) -> Rules {
+    let mut rules = args.rules.unwrap_or_default();
+    rules.late_surrender = flag_pair(
+        args.late_surrender,
+        args.no_late_surrender,
+        rules.late_surrender,
+    );
+    rules.early_surrender = flag_pair(
+        args.early_surrender,
+        args.no_early_surrender,
+        rules.early_surrender,
+    );
+    rules.dealer_hits_soft_17 = flag_pair(
+        args.dealer_hits_soft_17,
+        args.dealer_stands_soft_17,
+        rules.dealer_hits_soft_17,
+    );
+    rules.dealer_peeks = flag_pair(args.peek, args.no_peek, rules.dealer_peeks);
+    rules.das = flag_pair(args.das, args.no_das, rules.das);
+    if let Some(total) = args.dealer_stands {
+        rules.dealer_stands = total;
+    }
+    if let Some(ratio) = args.blackjack_pays {
+        rules.blackjack_pays = ratio;
+    }
+    rules
+}
+
+/// Looks up a named ruleset, listing the known ones if there's no such preset.
+fn parse_rules_preset(arg: &str) -> Result<Rules, String> {
+    Rules::preset(arg).ok_or_else(|| {
+        format!(
+            "`{}` isn't a known ruleset, try one of: {}",
+            arg,
+            RULES_PRESETS.join(", ")
+        )
+    })
+}
+
+/// Validates the total the dealer stands on, which must be between 12 and 21.
+fn parse_dealer_stands(arg: &str) -> Result<usize, String> {
+    let total: usize = arg
+        .parse()
+        .map_err(|_| format!("`{}` isn't a number", arg))?;
+    if !(MIN_DEALER_STANDS..=MAX_BLACKJACK).contains(&total) {
+        return Err(format!(
+            "the dealer must stand on a total between {} and {}",
+            MIN_DEALER_STANDS, MAX_BLACKJACK
+        ));
+    }
+    Ok(total)
+}
+
+/// Validates the starting credits and bet: both must be positive, and the bet can't be more than the
+/// credits.
+fn validate_bankroll(credits: isize, bet: isize) -> Result<(), String> {
+    if credits <= 0 {
+        return Err(String::from("starting credits must be positive"));
+    }
+    if bet <= 0 || bet > credits {
+        return Err(format!(
+            "the bet must be between 1 and the starting credits ({})",
+            credits
+        ));
+    }
+    Ok(())
+}
+
+/// Validates the table's limits: the minimum must be positive and no more than the maximum, and the
+/// starting `bet` has to be within them.
+fn validate_table_limits(limits: &TableLimits, bet: isize) -> Result<(), String> {
+    if limits.min <= 0 {
+        return Err(String::from("the table minimum must be positive"));
+    }
+    if limits.max.is_some_and(|max| max < limits.min) {
+        return Err(String::from(
+            "the table maximum can't be less than the minimum",
+        ));
+    }
+    if limits.clamp(bet) != (bet, false) {
+        return Err(String::from("the bet must be within the table's limits"));
+    }
+    Ok(())
+}
+
+/// Validates the walk-away rules: the target must be above the starting `credits`, and the stop-loss
+/// below them (but not negative).
+fn validate_walk_away(walk_away: &WalkAway, credits: isize) -> Result<(), String> {
+    if walk_away.target.is_some_and(|target| target <= credits) {
+        return Err(String::from(
+            "the target must be more than the starting credits",
+        ));
+    }
+    if walk_away
+        .stop_loss
+        .is_some_and(|stop_loss| stop_loss < 0 || stop_loss >= credits)
+    {
+        return Err(String::from(
+            "the stop-loss must be between 0 and the starting credits",
+        ));
+    }
+    Ok(())
 }
 
-/// Runs an interactive sub-menu for controlling bets. Checks against the current credit count.
-fn bet_menu(cur_bet: isize, cur_credits: isize) -> isize {
+/// Reads a scenario file into the deck it deals. Errors name the file, and the card that failed to parse.
+fn load_scenario(path: &Path) -> Result<Deck, String> {
+    let scenario = fs::read_to_string(path)
+        .map_err(|err| format!("can't read the scenario `{}`: {}", path.display(), err))?;
+    Deck::from_scenario(&scenario)
+        .map_err(|err| format!("invalid scenario `{}`, {}", path.display(), err))
+}
+
+/// Runs an interactive sub-menu for controlling bets. Checks against the current credit count and the
+/// table's `limits`. `prefix` names the player when several share the table.
+fn bet_menu(prefix: &str, cur_bet: isize, cur_credits: isize, limits: &TableLimits) -> isize {
     loop {
         print!(
-            "The current bet is ${}. New bet (enter to skip)? $",
-            cur_bet
+            "{}The current bet is ${}. New bet (enter to skip)? $",
+            prefix, cur_bet
         );
         let _ = io::stdout().flush();
 
@@ -58,17 +394,21 @@ fn bet_menu(cur_bet: isize, cur_credits: isize) -> isize {
             Err(_) => continue,
         };
 
-        match bet {
-            b if b > 0 && b <= cur_credits => return bet,
-            _ => println!("Invalid bet. Try again."),
+        match limits.check(bet, cur_credits) {
+            Ok(()) => return bet,
+            Err(msg) => println!("Invalid bet, {}. Try again.", msg),
         }
     }
 }
 
-/// Menu to continue or stop the game. Quits program if the user says no.
-fn play_again_menu(human_credits: isize) {
+/// Menu to continue or stop the game. Returns false if the player cashes out. `prefix` names the
+/// player when several share the table.
+fn play_again_menu(prefix: &str, human_credits: isize) -> bool {
     loop {
-        print!("Credits: ${} | Play again? (Y)es | (N)o > ", human_credits);
+        print!(
+            "{}Credits: ${} | Play again? (Y)es | (N)o > ",
+            prefix, human_credits
+        );
         let _ = io::stdout().flush();
 
         let mut input = String::new();
@@ -78,172 +418,1504 @@ fn play_again_menu(human_credits: isize) {
 
         // Quit the game from this sub-menu or set the old bet as the current.
         match input.trim().to_lowercase().as_str() {
-            "y" | "yes" => return,
+            "y" | "yes" => return true,
             "n" | "no" | "q" | "quit" => {
-                println!("Cashed out: ${}", human_credits);
-                process::exit(0);
+                println!("{}Cashed out: ${}", prefix, human_credits);
+                return false;
             }
             _ => (),
         }
     }
 }
 
-/// Initialize a game between a player and a dealer
-fn init_game(player: &mut Hand, dealer: &mut Hand, deck: &mut Deck) {
-    for _ in 0..2 {
-        player.hit(deck);
-        dealer.hit(deck);
-    }
+/// Reads the credits saved for each of `num_players` players, one line per player. Players without
+/// usable saved credits (including a missing file, or a player who went broke) start with
+/// `default_credits`.
+fn load_credits(path: &Path, num_players: usize, default_credits: isize) -> Vec<isize> {
+    let saved = fs::read_to_string(path).unwrap_or_default();
+    let mut lines = saved.lines();
+    (0..num_players)
+        .map(|_| {
+            lines
+                .next()
+                .and_then(|line| line.trim().parse().ok())
+                .filter(|credits| *credits > 0)
+                .unwrap_or(default_credits)
+        })
+        .collect()
 }
 
-/// Resets a game, providing a new deck of cards to work with
-fn reset_game(player: &mut Hand, dealer: &mut Hand) -> Deck {
-    player.clear_hand();
+/// Saves the credits of every player, one line per player
+fn save_credits(path: &Path, credits: &[isize]) {
+    let lines: Vec<String> = credits.iter().map(|c| c.to_string()).collect();
+    fs::write(path, lines.join("\n") + "\n").expect("Failed to save credits");
+}
+
+/// Resets a game, providing a new shoe of `num_decks` decks to work with, with `burn` cards burned
+fn reset_game(players: &mut [Hand], dealer: &mut Hand, num_decks: usize, burn: usize) -> Deck {
+    for player in players.iter_mut() {
+        player.clear_hand();
+    }
     dealer.clear_hand();
-    Deck::new()
+    let mut deck = Deck::with_decks(num_decks);
+    deck.burn(burn);
+    deck
 }
 
-/// Plays a game with the dealer at most `max_games` number of times. Bails early if the player runs out of money.
-/// This simulates a single "session" of a player sitting down to play a game.
-/// TODO: Add Monte Carlo and other betting strats
-/// TODO: Add support for a physical game by re-using the Deck to some degree.
-fn run_automated_match(max_games: usize) -> RunStats {
-    let mut deck = Deck::new();
-    let mut dealer = Hand::new("Dealer", Strategy::Dealer, DEALER_INFINITE_CREDITS);
-    let mut player = Hand::new(
-        "Auto Player",
-        Strategy::ProbabilityTable,
-        HUMAN_DEFAULT_CREDITS,
-    );
+/// Steps of the dealer's turn, in the order they happen.
+enum DealerEvent {
+    /// The down card is flipped face-up
+    RevealHoleCard(Card),
+    /// The dealer draws another card
+    Draw,
+    /// The dealer stands or busts
+    Done,
+}
 
-    let mut stats = RunStats::new();
+/// Plays out the dealer's turn, reporting each step to `on_event` so it can be animated.
+fn play_dealer_turn<F: FnMut(DealerEvent, &Hand)>(
+    dealer: &mut Hand,
+    deck: &mut Deck,
+    mut on_event: F,
+) {
+    let hole_card = dealer.reveal_hole_card();
+    on_event(DealerEvent::RevealHoleCard(hole_card), dealer);
+    loop {
+        let (stop, _) = dealer.play_once(deck, NO_BET_VALUE, dealer.get_up_card_rank());
+        if stop {
+            on_event(DealerEvent::Done, dealer);
+            break;
+        }
+        on_event(DealerEvent::Draw, dealer);
+    }
+}
 
-    for _ in 0..max_games {
-        init_game(&mut player, &mut dealer, &mut deck);
+/// Plays one interactive round after the initial deal for every human at the table, in turn, reading
+/// their moves from `input`. The dealer plays once, against all of them. Returns, for each human, the
+/// outcome and final bet of every hand they played. The dealer's turn pauses for `delay` at every step.
+fn play_table_round<R: BufRead>(
+    humans: &mut [Hand],
+    dealer: &mut Hand,
+    deck: &mut Deck,
+    bets: &[isize],
+    messages: &Messages,
+    input: &mut R,
+    delay: time::Duration,
+) -> Vec<Vec<(Outcome, isize)>> {
+    // Early surrender is offered before the dealer checks for BlackJack, so it beats a dealer natural.
+    if dealer.get_rules().early_surrender {
+        println!("{}", dealer);
+        for human in humans.iter_mut() {
+            println!("{}", human);
+            human.offer_early_surrender(dealer.get_up_card_rank(), input);
+        }
+    }
 
-        let bet = DEFAULT_BET_VALUE;
-        player.sub_credits(bet);
+    // Insurance (or even money, on a natural) is offered (and settled) before anyone acts.
+    let mut even_money = vec![false; humans.len()];
+    if dealer.get_up_card_rank() == Rank::Ace {
+        println!("{}", dealer);
+        for ((human, bet), took_even_money) in humans.iter_mut().zip(bets).zip(&mut even_money) {
+            if human.is_surrendered() {
+                continue;
+            }
+            println!("{}", human);
+            if let Some(taken) = human.offer_even_money(Rank::Ace, input) {
+                if taken {
+                    println!("Even money pays ${}!", 2 * bet);
+                }
+                *took_even_money = taken;
+                continue;
+            }
+            match play_insurance(human, dealer, *bet, input) {
+                (0, _) => (),
+                (_, 0) => println!("Insurance lost."),
+                (_, returned) => println!("Insurance pays ${}!", returned),
+            }
+        }
+    }
+
+    // Under peek rules, a dealer natural ends the round before anyone can act. Otherwise, it isn't
+    // found until the dealer's turn, after any doubles and splits.
+    if dealer.get_rules().dealer_peeks && dealer.peek_for_blackjack() {
+        dealer.show_hand();
+        println!("{}", dealer);
+        for human in humans.iter() {
+            println!("{}", human);
+        }
+        println!("{}", messages.get(MessageKey::DealerBlackJack));
+        return humans
+            .iter()
+            .zip(bets)
+            .zip(&even_money)
+            .map(|((human, bet), even_money)| vec![settle_hand(human, dealer, *bet, *even_money)])
+            .collect();
+    }
+
+    // A player natural is paid right away, without the dealer drawing for it.
+    if humans
+        .iter()
+        .all(|human| human.is_natural() || human.is_surrendered())
+    {
+        dealer.show_hand();
+        println!("{}", dealer);
+        for human in humans.iter() {
+            println!("{}", human);
+        }
+        return humans
+            .iter()
+            .zip(bets)
+            .zip(&even_money)
+            .map(|((human, bet), even_money)| vec![settle_hand(human, dealer, *bet, *even_money)])
+            .collect();
+    }
 
-        // Player control
-        let final_bet: isize;
-        loop {
-            let (stop, new_bet) = player.play_once(&mut deck, bet, dealer.get_up_card_rank());
-            if stop {
-                final_bet = new_bet;
-                break;
+    let up_card = dealer.get_up_card_rank();
+    let played: Vec<Vec<(Hand, isize)>> = humans
+        .iter_mut()
+        .zip(bets)
+        .map(|(human, bet)| {
+            if human.is_natural() || human.is_surrendered() {
+                return vec![(human.clone(), *bet)];
+            }
+            play_player_hands(human, deck, *bet, up_card, input, |hand| {
+                println!("{}", dealer);
+                println!("{}", hand);
+            })
+        })
+        .collect();
+    println!("+++++ {} +++++", messages.get(MessageKey::DealerTurn));
+    play_dealer_turn(dealer, deck, |event, dealer| {
+        // Add some delay to each step for human readability.
+        match event {
+            DealerEvent::RevealHoleCard(card) => {
+                thread::sleep(delay);
+                println!(
+                    "{} {}",
+                    messages.get(MessageKey::DealerReveal),
+                    card.styled(dealer.get_card_style())
+                );
+                println!("{}", dealer);
+            }
+            DealerEvent::Draw => {
+                thread::sleep(delay);
+                println!("{}", dealer);
             }
+            DealerEvent::Done => (),
         }
+    });
+
+    // Reprint every hand at the end to visualize the final result.
+    played
+        .iter()
+        .zip(&even_money)
+        .map(|(hands, even_money)| {
+            hands
+                .iter()
+                .map(|(hand, final_bet)| {
+                    println!("{}", hand);
+                    settle_hand(hand, dealer, *final_bet, *even_money)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Writes the results of every run as CSV, one row per run
+fn write_runs_csv<W: Write>(runs: &[RunStats], out: &mut W) {
+    writeln!(out, "num_games,wins,losses,pushes,remaining_credits").expect("I/O Error");
+    for run in runs {
+        writeln!(
+            out,
+            "{},{},{},{},{}",
+            run.num_games(),
+            run.wins(),
+            run.losses(),
+            run.pushes(),
+            run.remaining_credits()
+        )
+        .expect("I/O Error");
+    }
+}
 
-        // Dealer control
-        loop {
-            let (stop, _) = dealer.play_once(&mut deck, NO_BET_VALUE, dealer.get_up_card_rank());
-            if stop {
-                break;
+/// Prints a simulation's results as a JSON object, including every run's results when `verbose`
+#[cfg(feature = "json")]
+fn print_json_results(runs: &[RunStats], config: &SimConfig, verbose: bool) {
+    let total_stats = total_of(runs, config.starting_credits);
+    let mut results = total_stats.to_json();
+    results["decided_win_rate"] = serde_json::json!(total_stats.decided_win_rate());
+    results["break_even_win_rate"] = serde_json::json!(break_even_win_rate(&config.rules));
+    results["blackjack_pays"] = serde_json::json!(config.rules.blackjack_pays.to_string());
+    if verbose {
+        results["per_run"] = serde_json::json!(runs);
+    }
+    println!("{}", results);
+}
+
+/// JSON output isn't available without the `json` feature
+#[cfg(not(feature = "json"))]
+fn print_json_results(_runs: &[RunStats], _config: &SimConfig, _verbose: bool) {
+    eprintln!("JSON output requires building with the `json` feature.");
+    process::exit(1);
+}
+
+/// Runs a simulation in parallel, writing each run's results to `out` as a line of JSON as soon as the
+/// run finishes. Runs use the same schema as the per-run results of `--format json --verbose`.
+/// Results are not kept in memory.
+#[cfg(feature = "json")]
+fn stream_simulation_ndjson<W: Write + Send>(
+    config: &SimConfig,
+    run_seeds: &[u64],
+    out: &Mutex<W>,
+) {
+    run_seeds.par_iter().for_each(|seed| {
+        let line = serde_json::to_string(&run_automated_match(config, *seed))
+            .expect("Run results are serializable");
+        let mut out = out.lock().expect("Output lock poisoned");
+        writeln!(out, "{}", line).expect("I/O Error");
+        out.flush().expect("I/O Error");
+    });
+}
+
+/// NDJSON output isn't available without the `json` feature
+#[cfg(not(feature = "json"))]
+fn stream_simulation_ndjson<W: Write + Send>(
+    _config: &SimConfig,
+    _run_seeds: &[u64],
+    _out: &Mutex<W>,
+) {
+    eprintln!("NDJSON output requires building with the `json` feature.");
+    process::exit(1);
+}
+
+/// Plays the games of `config` at a table of `num_seats` automated players, recording only the results
+/// of the seat at `target_seat`, which always plays the probability table. Every other seat plays
+/// `others`. Only the number of games, rules, starting credits and base bet of `config` are used.
+/// Shoes are shuffled from `seed` alone, so calls with the same seed deal from identical shoes no
+/// matter how the other seats play, and `on_shoe` is called with each one before it's dealt. Used to
+/// measure how other players' decisions affect a seat.
+fn run_table_match<F: FnMut(&Deck)>(
+    num_seats: usize,
+    target_seat: usize,
+    others: Strategy,
+    seed: u64,
+    config: &SimConfig,
+    mut on_shoe: F,
+) -> RunStats {
+    let rules = config.rules;
+    let bet = config.base_bet;
+    let mut shoe_rng = StdRng::seed_from_u64(seed);
+    let mut dealer = Hand::new("Dealer", Strategy::Dealer, DEALER_INFINITE_CREDITS);
+    dealer.set_rules(rules);
+    let mut seats: Vec<Hand> = (0..num_seats)
+        .map(|i| match i {
+            i if i == target_seat => Hand::new(
+                "Target Player",
+                Strategy::ProbabilityTable,
+                config.starting_credits,
+            ),
+            _ => Hand::new("Other Player", others.clone(), OTHER_SEAT_CREDITS),
+        })
+        .collect();
+    for seat in seats.iter_mut() {
+        seat.set_rules(rules);
+    }
+
+    let mut stats = RunStats::new();
+    stats.record_credit_snapshot(seats[target_seat].get_credits());
+
+    for _ in 0..config.max_games {
+        let mut deck = Deck::with_rng(rules.decks.unwrap_or(TABLE_SHOE_DECKS), &mut shoe_rng);
+        on_shoe(&deck);
+        for _ in 0..2 {
+            for seat in seats.iter_mut() {
+                seat.hit_or_reshuffle(&mut deck);
             }
+            dealer.hit_or_reshuffle(&mut deck);
         }
 
-        let match_outcome = Hand::determine_outcome(&player, &dealer);
-        match match_outcome {
-            Outcome::Win => {
-                player.add_credits(final_bet * 2);
+        // Under peek rules, a dealer natural ends the round before anyone acts
+        let dealer_natural = rules.dealer_peeks && dealer.peek_for_blackjack();
+        let up_card = dealer.get_up_card_rank();
+        let mut seat_hands = Vec::with_capacity(num_seats);
+        for seat in seats.iter_mut() {
+            seat.sub_credits(bet);
+            if dealer_natural || seat.is_natural() {
+                seat_hands.push(vec![(seat.clone(), bet)]);
+                continue;
             }
-            Outcome::Loss => (),
-            Outcome::Push => {
-                player.add_credits(final_bet);
+            seat_hands.push(play_player_hands(
+                seat,
+                &mut deck,
+                bet,
+                up_card,
+                &mut io::empty(),
+                |_| (),
+            ));
+        }
+
+        if !dealer_natural {
+            loop {
+                let (stop, _) =
+                    dealer.play_once(&mut deck, NO_BET_VALUE, dealer.get_up_card_rank());
+                if stop {
+                    break;
+                }
             }
         }
-        stats.record_match_end(match_outcome);
 
-        // Broke players can't play
-        if player.get_credits() <= 0 {
-            break;
+        // Only the target seat's results are recorded
+        let target = &mut seats[target_seat];
+        let mut outcomes = Vec::new();
+        for (hand, final_bet) in seat_hands[target_seat].iter() {
+            let match_outcome = Hand::determine_outcome(hand, &dealer);
+            pay_out(target, &match_outcome, *final_bet);
+            outcomes.push(match_outcome);
         }
+        match outcomes.as_slice() {
+            [outcome] => stats.record_match_end(*outcome),
+            split => stats.record_split_result(split),
+        }
+        stats.record_credit_snapshot(target.get_credits());
 
-        // According to the internet, digital Blackjack machines reset the deck every game instance.
-        deck = reset_game(&mut player, &mut dealer);
+        let broke = target.get_credits() <= 0;
+        for seat in seats.iter_mut() {
+            seat.clear_hand();
+        }
+        dealer.clear_hand();
+        if broke {
+            stats.record_bust();
+            break;
+        }
     }
 
-    stats.record_credits(player.get_credits());
+    stats.record_credits(seats[target_seat].get_credits());
     stats
 }
 
+/// Runs the simulation of `config` once per betting strategy, each from the same `run_seeds`, and
+/// renders a table comparing their results. Strategies run one after the other, with their runs in
+/// parallel.
+fn compare_betting(config: &SimConfig, run_seeds: &[u64]) -> String {
+    let mut table = format!(
+        "{:<12}{:>12}{:>12}{:>12}{:>12}{:>12}\n",
+        "Betting", "Avg ending", "Std dev", "Busted out", "Avg peak", "Avg low"
+    );
+    let money = |amount: f64| format!("${:.2}", amount);
+    for strategy in BettingStrategy::iter() {
+        let strategy_config = SimConfig {
+            betting: *strategy,
+            ..config.clone()
+        };
+        let stats = simulate(&strategy_config, run_seeds);
+        table.push_str(&format!(
+            "{:<12}{:>12}{:>12}{:>12}{:>12}{:>12}\n",
+            strategy.to_string(),
+            money(stats.avg_credits()),
+            stats.credits_std_dev().map_or(String::from("n/a"), money),
+            format!("{:.2}%", 100f64 * stats.bust_out_rate()),
+            money(stats.avg_peak_credits()),
+            money(stats.avg_low_credits()),
+        ));
+    }
+    table
+}
+
+/// Two cards (without an Ace) that make a hard `total`, between 4 and 20
+fn hard_total_cards(total: usize) -> [Card; 2] {
+    let first = total.saturating_sub(10).max(2);
+    let rank = |value| Rank::from_value(value).expect("Values 2 through 10 are ranks");
+    [
+        Card {
+            suit: Suit::Clubs,
+            rank: rank(first),
+        },
+        Card {
+            suit: Suit::Hearts,
+            rank: rank(total - first),
+        },
+    ]
+}
+
+/// Single deck shuffled by `rng`, except that the opening deal gives the player `player_cards` and
+/// the dealer `up_card`.
+fn rigged_deck<R: Rng + ?Sized>(player_cards: &[Card; 2], up_card: Card, rng: &mut R) -> Deck {
+    let mut rest: Vec<Card> = Suit::iter()
+        .flat_map(|suit| {
+            Rank::iter().map(|rank| Card {
+                suit: *suit,
+                rank: *rank,
+            })
+        })
+        .collect();
+    for card in player_cards.iter().chain([&up_card]) {
+        let idx = rest
+            .iter()
+            .position(|c| c == card)
+            .expect("Card is in the deck");
+        rest.swap_remove(idx);
+    }
+    rest.shuffle(rng);
+    let down_card = rest.pop().expect("Deck has cards left");
+    // Dealt from the end: player, dealer's down card, player, dealer's up card
+    rest.extend([up_card, player_cards[1], down_card, player_cards[0]]);
+    Deck::from_cards(rest)
+}
+
+/// Plays the opening deal of `deck`, forcing the player's first move to be `action`. After a hit, the
+/// rest of the hand follows the probability table. Returns the net result in units of the bet.
+fn play_forced_action(deck: &mut Deck, action: Action, rules: Rules) -> isize {
+    let mut dealer = Hand::new("Dealer", Strategy::Dealer, DEALER_INFINITE_CREDITS);
+    dealer.set_rules(rules);
+    // Without credits, the player can't double or split after the forced move
+    let mut player = Hand::new("Auto Player", Strategy::ProbabilityTable, 0);
+    player.set_rules(rules);
+    init_game(slice::from_mut(&mut player), &mut dealer, deck);
+
+    let up_card = dealer.get_up_card_rank();
+    let mut bet = DEFAULT_BET_VALUE;
+    if !(rules.dealer_peeks && dealer.peek_for_blackjack()) {
+        match action {
+            Action::Hit => {
+                player.hit_or_reshuffle(deck);
+                while !player.play_once(deck, bet, up_card).0 {}
+            }
+            Action::DoubleDown => {
+                player.hit_or_reshuffle(deck);
+                bet *= 2;
+            }
+            _ => (),
+        }
+        while !dealer.play_once(deck, NO_BET_VALUE, up_card).0 {}
+    }
+    let outcome = Hand::determine_outcome(&player, &dealer);
+    rules.settle(&outcome, bet) - bet
+}
+
+/// Estimates the EV (in units of the bet) of `action` on a hard `total` against the dealer's
+/// `up_card`, over `trials` hands dealt from `seed`.
+fn simulate_action_ev(
+    total: usize,
+    up_card: Rank,
+    action: Action,
+    rules: Rules,
+    trials: usize,
+    seed: u64,
+) -> f64 {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let player_cards = hard_total_cards(total);
+    let up_card = Card {
+        suit: Suit::Spades,
+        rank: up_card,
+    };
+    let net: isize = (0..trials)
+        .map(|_| {
+            play_forced_action(
+                &mut rigged_deck(&player_cards, up_card, &mut rng),
+                action,
+                rules,
+            )
+        })
+        .sum();
+    net as f64 / trials as f64
+}
+
+/// Renders the simulated EV of hitting, standing and doubling down as grids of hard totals (rows) by
+/// dealer up card (columns), followed by the best move of every cell. Best moves that differ from the
+/// probability table are marked with a `*`. Every cell is simulated in parallel, with its own seed
+/// from `seed_rng`.
+fn ev_table(rules: Rules, trials: usize, seed_rng: &mut StdRng) -> String {
+    let actions = [Action::Hit, Action::Stand, Action::DoubleDown];
+    let up_cards = chart_up_cards();
+    let cells: Vec<(usize, Rank, u64)> = (CHART_MIN_TOTAL..=CHART_MAX_TOTAL)
+        .flat_map(|total| up_cards.iter().map(move |up_card| (total, *up_card)))
+        .map(|(total, up_card)| (total, up_card, seed_rng.gen()))
+        .collect();
+    // Every move in a cell is played on the same deals, so the moves are compared fairly
+    let evs: Vec<Vec<f64>> = cells
+        .par_iter()
+        .map(|(total, up_card, seed)| {
+            actions
+                .iter()
+                .map(|action| simulate_action_ev(*total, *up_card, *action, rules, trials, *seed))
+                .collect()
+        })
+        .collect();
+
+    let columns = up_cards.len();
+    let header: String = up_cards
+        .iter()
+        .map(|up| format!("{:>7}", up.to_string()))
+        .collect();
+    let mut report = String::new();
+    for (i, action) in actions.iter().enumerate() {
+        report.push_str(&format!("{}\n{:>3}{}\n", action, "", header));
+        for (row, row_evs) in cells.chunks(columns).zip(evs.chunks(columns)) {
+            report.push_str(&format!("{:>3}", row[0].0));
+            for cell_evs in row_evs.iter() {
+                report.push_str(&format!("{:>7.3}", cell_evs[i]));
+            }
+            report.push('\n');
+        }
+        report.push('\n');
+    }
+
+    report.push_str("Best move (* differs from the probability table)\n");
+    report.push_str(&format!("{:>3}{}\n", "", header));
+    for (row, row_evs) in cells.chunks(columns).zip(evs.chunks(columns)) {
+        report.push_str(&format!("{:>3}", row[0].0));
+        for ((total, up_card, _), cell_evs) in row.iter().zip(row_evs) {
+            let best = (0..actions.len())
+                .max_by(|a, b| cell_evs[*a].total_cmp(&cell_evs[*b]))
+                .map(|i| action_cell(&actions[i]).1)
+                .expect("There are moves to compare");
+            let table = action_cell(&get_action(*total, false, *up_card)).1;
+            let marker = if best == table { "" } else { "*" };
+            report.push_str(&format!("{:>7}", format!("{}{}", best, marker)));
+        }
+        report.push('\n');
+    }
+    report
+}
+
 /// Runs a single player text-based game or runs a parallelized simulation.
 fn main() {
     let args = CliArgs::parse();
+    let limits = TableLimits::new(args.table_min, args.table_max);
+    let walk_away = WalkAway {
+        target: args.target,
+        stop_loss: args.stop_loss,
+    };
+    if let Err(msg) = validate_bankroll(args.credits, args.bet)
+        .and_then(|()| validate_table_limits(&limits, args.bet))
+        .and_then(|()| validate_walk_away(&walk_away, args.credits))
+    {
+        CliArgs::command()
+            .error(ErrorKind::ValueValidation, msg)
+            .exit();
+    }
+
+    if let Some(path) = args.chart_html {
+        fs::write(path, strategy_chart_html()).expect("Failed to write strategy chart");
+        process::exit(0);
+    }
+
+    let rules = table_rules(&args);
+
+    let max_games = args
+        .hands
+        .map_or(DEFAULT_MAX_GAMES_PER_RUN, |hands| hands as usize);
+
+    // Every run gets its own seed, derived from the seed given (if any).
+    let mut seed_rng = StdRng::seed_from_u64(args.seed.unwrap_or_else(|| thread_rng().gen()));
+    let run_seeds: Vec<u64> = (0..args.runs.max(0)).map(|_| seed_rng.gen()).collect();
+
+    if args.ev_table {
+        let trials = match args.runs {
+            runs if runs > 0 => runs as usize,
+            _ => DEFAULT_EV_TABLE_TRIALS,
+        };
+        print!("{}", ev_table(rules, trials, &mut seed_rng));
+        process::exit(0);
+    }
+
+    let config = SimConfig {
+        strategy: Strategy::ProbabilityTable,
+        max_games,
+        explain: args.explain && !args.quiet,
+        rules,
+        carry_shoe: args.carry_shoe,
+        penetration: args.penetration,
+        audit: args.mistakes,
+        betting: args.betting,
+        progress: args.progress && !args.quiet,
+        starting_credits: args.credits,
+        base_bet: args.bet,
+        burn: args.burn,
+        limits,
+        pushes_end_streaks: args.pushes_end_streaks,
+        walk_away,
+    };
+
+    if args.runs > 0 && args.seats > 1 {
+        for others in [Strategy::ProbabilityTable, Strategy::Random] {
+            // Both comparisons use the same run seeds, so they play identical shoes.
+            let results: Vec<RunStats> = run_seeds
+                .par_iter()
+                .map(|seed| run_table_match(args.seats, 0, others.clone(), *seed, &config, |_| ()))
+                .collect();
+            println!("Other seats play {:?}:", others);
+            println!("{}", total_of(&results, args.credits));
+        }
+        process::exit(0);
+    }
+
+    if args.runs > 0 && args.count_cards {
+        // Counting is only useful when the shoe isn't replaced every game
+        let basic_config = SimConfig {
+            carry_shoe: true,
+            ..config
+        };
+        let counter_config = SimConfig {
+            strategy: Strategy::CardCounter,
+            ..basic_config.clone()
+        };
+        let basic_stats = simulate(&basic_config, &run_seeds);
+        let counter_stats = simulate(&counter_config, &run_seeds);
+        println!("Basic strategy:\n{}", basic_stats);
+        println!("Card counter:\n{}", counter_stats);
+        println!(
+            "Card counter edge over basic strategy: ${:+.2} per run",
+            counter_stats.avg_credits() - basic_stats.avg_credits()
+        );
+        process::exit(0);
+    }
+
+    if args.runs > 0 && args.compare_betting {
+        print!("{}", compare_betting(&config, &run_seeds));
+        process::exit(0);
+    }
+
+    if args.runs > 0 && args.ndjson {
+        stream_simulation_ndjson(&config, &run_seeds, &Mutex::new(io::stdout()));
+        process::exit(0);
+    }
 
     if args.runs > 0 {
-        let mut total_stats = TotalRunStats::new(HUMAN_DEFAULT_CREDITS);
-        // Each game is run in a parallel using rayon's `map()` functionality.
-        let results: Vec<RunStats> = (0..args.runs)
-            .into_par_iter()
-            .map(|_| run_automated_match(DEFAULT_MAX_GAMES_PER_RUN))
-            .collect();
-        for stats in results {
-            total_stats.add_run(stats);
+        let runs = simulate_runs(&config, &run_seeds);
+        if let Some(path) = args.csv {
+            let mut file = fs::File::create(path).expect("Failed to create the CSV file");
+            write_runs_csv(&runs, &mut file);
+        }
+        if args.format == OutputFormat::Json {
+            print_json_results(&runs, &config, args.verbose);
+            process::exit(0);
         }
+        let total_stats = total_of(&runs, config.starting_credits);
+        if args.quiet {
+            print!("{}", total_stats);
+            process::exit(0);
+        }
+        println!("BlackJack pays {}", rules.blackjack_pays);
         println!("{}", total_stats);
+        println!(
+            "Win rate excluding pushes: {:.2}% | Needed to break even: {:.2}%",
+            100f64 * total_stats.decided_win_rate(),
+            100f64 * break_even_win_rate(&rules),
+        );
+        if let Some(bin_width) = args.histogram {
+            println!(
+                "\nEnding credits:\n{}",
+                CreditHistogram::new(&runs, bin_width)
+            );
+        }
         process::exit(0);
     }
 
-    let mut deck = Deck::new();
+    if args.quiet {
+        CliArgs::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--quiet only applies to simulations, the human game needs its prompts",
+            )
+            .exit();
+    }
+
+    // Several players need a shoe, so the cards don't run out
+    let num_decks = match (rules.decks, args.players) {
+        (Some(decks), _) => decks,
+        (None, 1) => 1,
+        (None, _) => TABLE_SHOE_DECKS,
+    };
+    let mut deck = match &args.scenario {
+        Some(path) => load_scenario(path).unwrap_or_else(|msg| {
+            CliArgs::command()
+                .error(ErrorKind::ValueValidation, msg)
+                .exit()
+        }),
+        None => {
+            let mut deck = Deck::with_decks(num_decks);
+            deck.burn(args.burn);
+            deck
+        }
+    };
     let mut dealer = Hand::new("Dealer", Strategy::Dealer, DEALER_INFINITE_CREDITS);
-    let mut human = Hand::new("Player 1", Strategy::Human, HUMAN_DEFAULT_CREDITS);
+    let card_style = if args.color {
+        CardStyle::Color
+    } else {
+        CardStyle::Plain
+    };
+    dealer.set_rules(rules);
+    dealer.set_card_style(card_style);
+    let messages = Messages::default();
+    // Credits of every seat, which are saved as players cash out
+    let mut bankrolls = match &args.load {
+        Some(path) => load_credits(path, args.players as usize, args.credits),
+        None => vec![args.credits; args.players as usize],
+    };
+    let mut humans: Vec<Hand> = bankrolls
+        .iter()
+        .enumerate()
+        .map(|(i, credits)| {
+            let mut human = Hand::new(&format!("Player {}", i + 1), Strategy::Human, *credits);
+            human.set_messages(messages.clone());
+            human.set_explain(args.explain);
+            human.set_practice(args.practice);
+            human.set_auto_stand(!args.no_auto_stand);
+            human.set_card_style(card_style);
+            human.set_rules(rules);
+            human
+        })
+        .collect();
+    // Menus only name the player when there's more than one
+    let prefix = |human: &Hand| match args.players {
+        1 => String::new(),
+        _ => format!("{} | ", human.get_name()),
+    };
 
-    // Current bet tracks bets between games for easier user interaction.
-    let mut cur_bet: isize = DEFAULT_BET_VALUE;
+    // Current bets track bets between games for easier user interaction.
+    let mut cur_bets: Vec<isize> = vec![args.bet; humans.len()];
+    // Seat (index into `bankrolls`) of every player still at the table
+    let mut seats: Vec<usize> = (0..humans.len()).collect();
 
     let mut game_cntr = 1;
     loop {
         // Deal initial cards
-        init_game(&mut human, &mut dealer, &mut deck);
+        init_game(&mut humans, &mut dealer, &mut deck);
 
-        // Bet must occur before cards are shown
-        cur_bet = bet_menu(cur_bet, human.get_credits());
-        human.sub_credits(cur_bet);
+        // Bets must occur before cards are shown
+        for (human, cur_bet) in humans.iter_mut().zip(cur_bets.iter_mut()) {
+            *cur_bet = bet_menu(&prefix(human), *cur_bet, human.get_credits(), &limits);
+            human.sub_credits(*cur_bet);
+        }
 
         println!("\n########## Game #{:<4} ##########\n", game_cntr);
 
-        // Final bet is used in betting calculations as it accounts for a player doubling down.
-        let final_bet;
-        loop {
-            println!("{}", dealer);
-            println!("{}", human);
-            let (stop, new_bet) = human.play_once(&mut deck, cur_bet, dealer.get_up_card_rank());
-            if stop {
-                final_bet = new_bet;
-                break;
-            }
-        }
-        println!("+++++ Dealer's Turn +++++");
-        loop {
-            thread::sleep(time::Duration::from_secs(1));
-            dealer.show_hand();
-            println!("{}", dealer);
-            let (stop, _) = dealer.play_once(&mut deck, NO_BET_VALUE, dealer.get_up_card_rank());
-            if stop {
-                break;
+        let stdin = io::stdin();
+        let results = play_table_round(
+            &mut humans,
+            &mut dealer,
+            &mut deck,
+            &cur_bets,
+            &messages,
+            &mut stdin.lock(),
+            time::Duration::from_millis(args.delay_ms),
+        );
+
+        // Adjust each player's credits based on the outcome of each hand.
+        for (human, hand_results) in humans.iter_mut().zip(results) {
+            for (outcome, final_bet) in hand_results {
+                pay_out(human, &outcome, final_bet);
+                println!("{}{}", prefix(human), messages.outcome_banner(&outcome));
             }
         }
-        // Reprint the human's hand at the end to visualize the final result.
-        println!("{}", human);
 
-        // Determine the outcome and adjust the player's credits.
-        match Hand::determine_outcome(&human, &dealer) {
-            Outcome::Win => {
-                human.add_credits(final_bet * 2);
-                println!("----- Winner! -----");
+        // Players who cash out leave the table. Everyone cashes out once the session is over.
+        let session_over = args.hands.is_some_and(|hands| game_cntr >= hands);
+        let mut i = 0;
+        while i < humans.len() {
+            if session_over {
+                println!(
+                    "{}Cashed out: ${}",
+                    prefix(&humans[i]),
+                    humans[i].get_credits()
+                );
+            } else if play_again_menu(&prefix(&humans[i]), humans[i].get_credits()) {
+                i += 1;
+                continue;
             }
-            Outcome::Loss => println!("----- Loser!  -----"),
-            Outcome::Push => {
-                human.add_credits(final_bet);
-                println!("-----  Push.  -----");
+            bankrolls[seats[i]] = humans[i].get_credits();
+            if let Some(path) = &args.save {
+                save_credits(path, &bankrolls);
             }
+            humans.remove(i);
+            cur_bets.remove(i);
+            seats.remove(i);
         }
-
-        play_again_menu(human.get_credits());
-        // If we've gotten to this point, the user has NOT quit, so we must
+        if humans.is_empty() {
+            process::exit(0);
+        }
+        // If we've gotten to this point, someone has NOT quit, so we must
         // reset for the next round.
-        deck = reset_game(&mut human, &mut dealer);
+        let fresh_deck = reset_game(&mut humans, &mut dealer, num_decks, args.burn);
+        // A scenario keeps dealing its cards over the following games, until they run out
+        if args.scenario.is_none() || deck.remaining() == 0 {
+            deck = fresh_deck;
+        }
         game_cntr += 1;
     }
 }
+
+/// A dealer natural resolves the round before the human is prompted to act.
+#[rstest]
+fn dealer_natural_resolves_before_human_acts() {
+    let mut deck = Deck::new();
+    let mut human = Hand::from_vector(
+        "Player 1",
+        Strategy::Human,
+        vec![
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Ten,
+            },
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Seven,
+            },
+        ],
+    );
+    let mut dealer = Hand::from_vector(
+        "Dealer",
+        Strategy::Dealer,
+        vec![
+            Card {
+                suit: Suit::Spades,
+                rank: Rank::King,
+            },
+            Card {
+                suit: Suit::Spades,
+                rank: Rank::Ace,
+            },
+        ],
+    );
+    let results = play_table_round(
+        slice::from_mut(&mut human),
+        &mut dealer,
+        &mut deck,
+        &[DEFAULT_BET_VALUE],
+        &Messages::default(),
+        &mut "n\nh\n".as_bytes(),
+        time::Duration::ZERO,
+    );
+    assert_eq!(results, vec![vec![(Outcome::Loss, DEFAULT_BET_VALUE)]]);
+    // Insurance was declined, but the scripted "hit" was never consumed
+    assert_eq!(human.final_value(), 17);
+}
+
+/// A player natural is paid 3:2 without the dealer drawing, even when the player could hit 21.
+#[rstest]
+fn player_natural_resolves_at_deal() {
+    let mut deck = Deck::new();
+    let mut human = Hand::from_vector(
+        "Player 1",
+        Strategy::Human,
+        vec![
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Ace,
+            },
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Queen,
+            },
+        ],
+    );
+    human.set_auto_stand(false);
+    let mut dealer = Hand::from_vector(
+        "Dealer",
+        Strategy::Dealer,
+        vec![
+            Card {
+                suit: Suit::Spades,
+                rank: Rank::Ten,
+            },
+            Card {
+                suit: Suit::Spades,
+                rank: Rank::Six,
+            },
+        ],
+    );
+    assert!(human.is_natural());
+    assert!(!dealer.is_natural());
+    let results = play_table_round(
+        slice::from_mut(&mut human),
+        &mut dealer,
+        &mut deck,
+        &[DEFAULT_BET_VALUE],
+        &Messages::default(),
+        &mut "h\n".as_bytes(),
+        time::Duration::ZERO,
+    );
+    assert_eq!(results, vec![vec![(Outcome::BlackJack, DEFAULT_BET_VALUE)]]);
+    assert_eq!(human.get_cards().len(), 2);
+    assert_eq!(dealer.get_cards().len(), 2);
+    assert_eq!(deck.remaining(), 52);
+}
+
+/// Every player at the table is dealt in turn before the dealer, twice around
+#[rstest]
+fn deal_to_several_players() {
+    let card = |rank| Card {
+        suit: Suit::Spades,
+        rank,
+    };
+    // Dealt from the end
+    let mut deck = Deck::from_cards(
+        [
+            Rank::Six,
+            Rank::Five,
+            Rank::Four,
+            Rank::Three,
+            Rank::Two,
+            Rank::Ace,
+        ]
+        .map(card)
+        .to_vec(),
+    );
+    let mut players = vec![
+        Hand::new("Player 1", Strategy::Human, HUMAN_DEFAULT_CREDITS),
+        Hand::new("Player 2", Strategy::Human, HUMAN_DEFAULT_CREDITS),
+    ];
+    let mut dealer = Hand::new("Dealer", Strategy::Dealer, DEALER_INFINITE_CREDITS);
+    init_game(&mut players, &mut dealer, &mut deck);
+    let ranks = |hand: &Hand| hand.iter_cards().map(|c| c.rank).collect::<Vec<_>>();
+    assert_eq!(ranks(&players[0]), vec![Rank::Ace, Rank::Four]);
+    assert_eq!(ranks(&players[1]), vec![Rank::Two, Rank::Five]);
+    assert_eq!(ranks(&dealer), vec![Rank::Three, Rank::Six]);
+}
+
+/// A dealer natural settles every player at the table at once
+#[rstest]
+fn dealer_natural_settles_whole_table() {
+    let card = |rank| Card {
+        suit: Suit::Hearts,
+        rank,
+    };
+    let mut deck = Deck::new();
+    let mut humans = vec![
+        Hand::from_vector(
+            "Player 1",
+            Strategy::Human,
+            vec![card(Rank::Ace), card(Rank::King)],
+        ),
+        Hand::from_vector(
+            "Player 2",
+            Strategy::Human,
+            vec![card(Rank::Ten), card(Rank::Seven)],
+        ),
+    ];
+    let mut dealer = Hand::from_vector(
+        "Dealer",
+        Strategy::Dealer,
+        vec![card(Rank::Queen), card(Rank::Ace)],
+    );
+    let results = play_table_round(
+        &mut humans,
+        &mut dealer,
+        &mut deck,
+        &[DEFAULT_BET_VALUE, 2],
+        &Messages::default(),
+        &mut "n\nn\nh\n".as_bytes(),
+        time::Duration::ZERO,
+    );
+    assert_eq!(
+        results,
+        vec![
+            vec![(Outcome::Push, DEFAULT_BET_VALUE)],
+            vec![(Outcome::Loss, 2)]
+        ]
+    );
+    assert_eq!(humans[1].final_value(), 17);
+}
+
+/// Even money pays 1:1 on a natural against a dealer Ace, whether or not the dealer has a natural.
+/// Declining it pushes against a dealer natural.
+#[rstest]
+#[case(Rank::King, "e\n", Outcome::Win)]
+#[case(Rank::Six, "e\n", Outcome::Win)]
+#[case(Rank::King, "n\n", Outcome::Push)]
+#[case(Rank::Six, "n\n", Outcome::BlackJack)]
+fn even_money(#[case] down_card: Rank, #[case] answer: &str, #[case] expected: Outcome) {
+    let card = |rank| Card {
+        suit: Suit::Diamonds,
+        rank,
+    };
+    let mut deck = Deck::new();
+    let mut human = Hand::from_vector(
+        "Player 1",
+        Strategy::Human,
+        vec![card(Rank::Ace), card(Rank::Jack)],
+    );
+    let mut dealer = Hand::from_vector(
+        "Dealer",
+        Strategy::Dealer,
+        vec![card(down_card), card(Rank::Ace)],
+    );
+    let results = play_table_round(
+        slice::from_mut(&mut human),
+        &mut dealer,
+        &mut deck,
+        &[2],
+        &Messages::default(),
+        &mut answer.as_bytes(),
+        time::Duration::ZERO,
+    );
+    assert_eq!(results, vec![vec![(expected, 2)]]);
+    assert_eq!(deck.remaining(), 52);
+}
+
+/// Under US rules, the dealer peeks and a natural only takes the original bet. Without the peek, the
+/// player doubles before the natural is found, and loses the doubled bet.
+#[rstest]
+#[case(true, 2)]
+#[case(false, 4)]
+fn dealer_peek_rules(#[case] dealer_peeks: bool, #[case] expected_bet: isize) {
+    let card = |rank| Card {
+        suit: Suit::Hearts,
+        rank,
+    };
+    let rules = Rules {
+        dealer_peeks,
+        ..Rules::default()
+    };
+    let mut deck = Deck::new();
+    let mut human = Hand::from_vector(
+        "Player 1",
+        Strategy::Human,
+        vec![card(Rank::Six), card(Rank::Five)],
+    );
+    human.set_rules(rules);
+    let mut dealer = Hand::from_vector(
+        "Dealer",
+        Strategy::Dealer,
+        vec![card(Rank::Ace), card(Rank::King)],
+    );
+    dealer.set_rules(rules);
+    assert!(dealer.peek_for_blackjack());
+    let results = play_table_round(
+        slice::from_mut(&mut human),
+        &mut dealer,
+        &mut deck,
+        &[2],
+        &Messages::default(),
+        &mut "d\n".as_bytes(),
+        time::Duration::ZERO,
+    );
+    assert_eq!(results, vec![vec![(Outcome::Loss, expected_bet)]]);
+    assert_eq!(dealer.get_cards().len(), 2);
+}
+
+/// Early surrender is offered before the peek, so the player keeps half the bet against a dealer
+/// natural. Late surrender comes too late to be offered.
+#[rstest]
+#[case(true, "r\n", Outcome::Surrender, 1)]
+#[case(false, "r\n", Outcome::Loss, 0)]
+fn early_surrender_beats_peek(
+    #[case] early_surrender: bool,
+    #[case] answer: &str,
+    #[case] expected: Outcome,
+    #[case] returned: isize,
+) {
+    let card = |rank| Card {
+        suit: Suit::Clubs,
+        rank,
+    };
+    let rules = Rules {
+        early_surrender,
+        late_surrender: !early_surrender,
+        ..Rules::default()
+    };
+    let mut deck = Deck::new();
+    let mut human = Hand::from_vector(
+        "Player 1",
+        Strategy::Human,
+        vec![card(Rank::Ten), card(Rank::Six)],
+    );
+    human.set_rules(rules);
+    let mut dealer = Hand::from_vector(
+        "Dealer",
+        Strategy::Dealer,
+        vec![card(Rank::King), card(Rank::Ace)],
+    );
+    dealer.set_rules(rules);
+    // Insurance is declined when it is offered
+    let answers = format!("{}n\n", answer);
+    let results = play_table_round(
+        slice::from_mut(&mut human),
+        &mut dealer,
+        &mut deck,
+        &[2],
+        &Messages::default(),
+        &mut answers.as_bytes(),
+        time::Duration::ZERO,
+    );
+    assert_eq!(results, vec![vec![(expected, 2)]]);
+    assert_eq!(rules.settle(&expected, 2), returned);
+}
+
+/// The dealer's hole card is revealed before the first draw.
+#[rstest]
+fn dealer_reveals_before_drawing() {
+    let mut deck = Deck::new();
+    let mut dealer = Hand::from_vector(
+        "Dealer",
+        Strategy::Dealer,
+        vec![
+            Card {
+                suit: Suit::Spades,
+                rank: Rank::Six,
+            },
+            Card {
+                suit: Suit::Spades,
+                rank: Rank::Five,
+            },
+        ],
+    );
+    let mut events = Vec::new();
+    play_dealer_turn(&mut dealer, &mut deck, |event, _| events.push(event));
+    assert!(matches!(events[0], DealerEvent::RevealHoleCard(_)));
+    assert!(matches!(events[1], DealerEvent::Draw));
+    assert!(matches!(events.last(), Some(DealerEvent::Done)));
+}
+
+/// Both table configurations deal from identical shoes, and only the target seat is recorded.
+#[rstest]
+fn table_match_shares_shoes() {
+    let config = SimConfig {
+        max_games: 5,
+        ..SimConfig::default()
+    };
+    let shoes: Vec<Vec<String>> = [Strategy::ProbabilityTable, Strategy::Random]
+        .into_iter()
+        .map(|others| {
+            let mut shoes = Vec::new();
+            let stats = run_table_match(4, 0, others, 7, &config, |shoe| {
+                shoes.push(shoe.to_string())
+            });
+            // 1 recorded result per game, not 1 per seat
+            assert_eq!(stats.net_units().len(), 5);
+            shoes
+        })
+        .collect();
+    assert_eq!(shoes[0].len(), 5);
+    assert_eq!(shoes[0], shoes[1]);
+}
+
+/// Basic strategy neighbors have the credits to double down and split, like real players
+#[rstest]
+fn other_seats_can_double_and_split() {
+    let other_seat = |first, second| {
+        let mut other = Hand::new(
+            "Other Player",
+            Strategy::ProbabilityTable,
+            OTHER_SEAT_CREDITS,
+        );
+        other.set_splits_remaining(Rules::default().max_splits);
+        for rank in [first, second] {
+            other.add_card(Card {
+                suit: Suit::Hearts,
+                rank,
+            });
+        }
+        other
+    };
+    assert!(other_seat(Rank::Eight, Rank::Eight).can_split(DEFAULT_BET_VALUE));
+    assert!(other_seat(Rank::Five, Rank::Six).can_double_down(DEFAULT_BET_VALUE));
+}
+
+/// The table match plays by the configured rules: a 6:5 natural never pays more than a 3:2 one.
+/// The bankroll is large enough that the payouts can't change which hands the target can double.
+#[rstest]
+fn table_match_uses_rules() {
+    let credits = |blackjack_pays: &str, seed| {
+        let config = SimConfig {
+            base_bet: 10,
+            starting_credits: 100_000,
+            rules: Rules {
+                blackjack_pays: blackjack_pays.parse().unwrap(),
+                ..Rules::default()
+            },
+            ..SimConfig::default()
+        };
+        run_table_match(3, 0, Strategy::ProbabilityTable, seed, &config, |_| ()).remaining_credits()
+    };
+    let pairs: Vec<(isize, isize)> = (0..10)
+        .map(|seed| (credits("3:2", seed), credits("6:5", seed)))
+        .collect();
+    assert!(pairs
+        .iter()
+        .all(|(three_two, six_five)| six_five <= three_two));
+    assert!(pairs
+        .iter()
+        .any(|(three_two, six_five)| six_five < three_two));
+}
+
+/// Strategies compared on fixed deals start every round with the same hands, but their draws differ.
+#[rstest]
+fn fixed_deals_match_starting_hands() {
+    let mut basic_rng = StdRng::seed_from_u64(1);
+    let mut random_rng = StdRng::seed_from_u64(2);
+    for round in 0..3 {
+        let mut basic_deck = Deck::with_fixed_deal(1, round, &mut basic_rng);
+        let mut random_deck = Deck::with_fixed_deal(1, round, &mut random_rng);
+        let mut basic = Hand::new("Basic", Strategy::ProbabilityTable, HUMAN_DEFAULT_CREDITS);
+        let mut random = Hand::new("Random", Strategy::Random, HUMAN_DEFAULT_CREDITS);
+        let mut basic_dealer = Hand::new("Dealer", Strategy::Dealer, DEALER_INFINITE_CREDITS);
+        let mut random_dealer = Hand::new("Dealer", Strategy::Dealer, DEALER_INFINITE_CREDITS);
+        init_game(
+            slice::from_mut(&mut basic),
+            &mut basic_dealer,
+            &mut basic_deck,
+        );
+        init_game(
+            slice::from_mut(&mut random),
+            &mut random_dealer,
+            &mut random_deck,
+        );
+        basic_dealer.show_hand();
+        random_dealer.show_hand();
+
+        assert_eq!(basic.final_value(), random.final_value());
+        assert_eq!(basic_dealer.to_string(), random_dealer.to_string());
+        assert_ne!(basic_deck.to_string(), random_deck.to_string());
+    }
+}
+
+/// Shoe penetration must be a fraction of the shoe
+#[rstest]
+#[case("0.75", Some(0.75))]
+#[case("0", None)]
+#[case("1", None)]
+#[case("most", None)]
+fn check_parse_penetration(#[case] arg: &str, #[case] expected: Option<f64>) {
+    assert_eq!(parse_penetration(arg).ok(), expected)
+}
+
+/// Streaming writes one JSON object per run, one per line, in the same schema as verbose JSON output
+#[cfg(feature = "json")]
+#[rstest]
+fn ndjson_one_object_per_run() {
+    let config = SimConfig {
+        max_games: 5,
+        ..SimConfig::default()
+    };
+    let out = Mutex::new(Vec::new());
+    stream_simulation_ndjson(&config, &[1, 2, 3, 4], &out);
+    let text = String::from_utf8(out.into_inner().unwrap()).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 4);
+    for line in lines {
+        let run: serde_json::Value = serde_json::from_str(line).unwrap();
+        let expected = serde_json::to_value(RunStats::new()).unwrap();
+        let keys = |value: &serde_json::Value| {
+            value
+                .as_object()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&run), keys(&expected));
+    }
+}
+
+/// The CSV has a header, then a row per run
+#[rstest]
+fn runs_csv() {
+    let mut run = RunStats::new();
+    run.record_match_end(Outcome::Win);
+    run.record_match_end(Outcome::Push);
+    run.record_credits(101);
+    let mut out = Vec::new();
+    write_runs_csv(&[run, RunStats::new()], &mut out);
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "num_games,wins,losses,pushes,remaining_credits\n2,1,0,1,101\n0,0,0,0,0\n"
+    );
+}
+
+/// Walk-away rules are checked against the starting credits
+#[rstest]
+#[case(None, None, true)]
+#[case(Some(200), Some(50), true)]
+#[case(Some(100), None, false)]
+#[case(None, Some(100), false)]
+#[case(None, Some(-1), false)]
+fn check_validate_walk_away(
+    #[case] target: Option<isize>,
+    #[case] stop_loss: Option<isize>,
+    #[case] valid: bool,
+) {
+    let walk_away = WalkAway { target, stop_loss };
+    assert_eq!(
+        validate_walk_away(&walk_away, HUMAN_DEFAULT_CREDITS).is_ok(),
+        valid
+    );
+}
+
+/// Saved credits are loaded back per player, falling back to the default credits
+#[rstest]
+fn save_and_load_credits() {
+    let path = std::env::temp_dir().join(format!("rust_blackjack_credits_{}", process::id()));
+    assert_eq!(
+        load_credits(&path, 1, HUMAN_DEFAULT_CREDITS),
+        vec![HUMAN_DEFAULT_CREDITS]
+    );
+
+    save_credits(&path, &[250, 0]);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "250\n0\n");
+    // Broke and missing players start over
+    assert_eq!(
+        load_credits(&path, 3, HUMAN_DEFAULT_CREDITS),
+        vec![250, HUMAN_DEFAULT_CREDITS, HUMAN_DEFAULT_CREDITS]
+    );
+
+    fs::write(&path, "lots\n").unwrap();
+    assert_eq!(
+        load_credits(&path, 1, HUMAN_DEFAULT_CREDITS),
+        vec![HUMAN_DEFAULT_CREDITS]
+    );
+    fs::remove_file(&path).unwrap();
+}
+
+/// Bets have to fit in the starting credits
+#[rstest]
+#[case(100, 1, true)]
+#[case(100, 100, true)]
+#[case(100, 101, false)]
+#[case(100, 0, false)]
+#[case(100, -5, false)]
+#[case(0, 1, false)]
+fn check_validate_bankroll(#[case] credits: isize, #[case] bet: isize, #[case] valid: bool) {
+    assert_eq!(validate_bankroll(credits, bet).is_ok(), valid);
+}
+
+/// Scenario files deal their cards in order, and report where they fail to parse
+#[rstest]
+fn scenario_file() {
+    let path = std::env::temp_dir().join(format!("scenario_{}.txt", process::id()));
+    fs::write(&path, "AS 9H\nKD 7C").expect("I/O Error");
+    let mut deck = load_scenario(&path).unwrap();
+    let mut players = vec![Hand::new(
+        "Player 1",
+        Strategy::Human,
+        HUMAN_DEFAULT_CREDITS,
+    )];
+    let mut dealer = Hand::new("Dealer", Strategy::Dealer, DEALER_INFINITE_CREDITS);
+    init_game(&mut players, &mut dealer, &mut deck);
+    assert_eq!(players[0].final_value(), 21);
+    assert_eq!(dealer.final_value(), 16);
+
+    fs::write(&path, "AS 9H\nKD 7Q").expect("I/O Error");
+    let err = load_scenario(&path).err().unwrap();
+    assert!(err.ends_with("line 2, card 4 (`7Q`): `Q` is not a suit (H, D, S or C)"));
+    fs::remove_file(&path).expect("I/O Error");
+}
+
+/// The dealer's stand total is limited to a sane range
+#[rstest]
+#[case("17", Ok(17))]
+#[case("12", Ok(12))]
+#[case("21", Ok(21))]
+#[case("11", Err(()))]
+#[case("22", Err(()))]
+#[case("soft", Err(()))]
+fn check_parse_dealer_stands(#[case] arg: &str, #[case] expected: Result<usize, ()>) {
+    assert_eq!(parse_dealer_stands(arg).map_err(|_| ()), expected);
+}
+
+/// Rule options override a preset both ways, and the last of an on/off pair wins
+#[rstest]
+#[case(&["--rules", "atlantic-city"], true, true)]
+#[case(&["--rules", "atlantic-city", "--no-late-surrender"], false, true)]
+#[case(&["--rules", "european"], false, false)]
+#[case(&["--rules", "european", "--peek"], false, true)]
+#[case(&["--no-peek", "--peek", "--late-surrender"], true, true)]
+#[case(&["--peek", "--no-peek"], false, false)]
+fn check_table_rules(
+    #[case] options: &[&str],
+    #[case] late_surrender: bool,
+    #[case] dealer_peeks: bool,
+) {
+    let args = CliArgs::try_parse_from(["rust_blackjack", "10"].iter().chain(options)).unwrap();
+    let rules = table_rules(&args);
+    assert_eq!(rules.late_surrender, late_surrender);
+    assert_eq!(rules.dealer_peeks, dealer_peeks);
+}
+
+/// Rule presets are looked up by name, and a typo lists the known ones
+#[rstest]
+fn check_parse_rules_preset() {
+    assert!(!parse_rules_preset("european").unwrap().dealer_peeks);
+    let err = parse_rules_preset("vegas").unwrap_err();
+    assert!(err.contains("vegas-strip, atlantic-city, european"));
+}
+
+/// Table limits have to make sense, and fit the starting bet
+#[rstest]
+#[case(1, None, 1, true)]
+#[case(5, Some(100), 10, true)]
+#[case(5, Some(100), 1, false)]
+#[case(5, Some(100), 200, false)]
+#[case(50, Some(10), 20, false)]
+#[case(0, None, 1, false)]
+fn check_validate_table_limits(
+    #[case] min: isize,
+    #[case] max: Option<isize>,
+    #[case] bet: isize,
+    #[case] valid: bool,
+) {
+    assert_eq!(
+        validate_table_limits(&TableLimits::new(min, max), bet).is_ok(),
+        valid
+    );
+}
+
+/// A rigged deck deals the forced hard total and up card, and standing on 20 against a 6 beats
+/// hitting it.
+#[rstest]
+fn forced_action_ev() {
+    let player_cards = hard_total_cards(20);
+    let up_card = Card {
+        suit: Suit::Spades,
+        rank: Rank::Six,
+    };
+    let mut deck = rigged_deck(&player_cards, up_card, &mut StdRng::seed_from_u64(1));
+    let mut player = Hand::new("Auto Player", Strategy::ProbabilityTable, 0);
+    let mut dealer = Hand::new("Dealer", Strategy::Dealer, DEALER_INFINITE_CREDITS);
+    init_game(slice::from_mut(&mut player), &mut dealer, &mut deck);
+    assert_eq!(player.get_cards(), player_cards);
+    assert_eq!(dealer.get_cards()[UP_CARD_IDX], up_card);
+    assert_eq!(deck.total(), 52);
+
+    let rules = Rules::default();
+    let stand = simulate_action_ev(20, Rank::Six, Action::Stand, rules, 2000, 7);
+    let hit = simulate_action_ev(20, Rank::Six, Action::Hit, rules, 2000, 7);
+    assert!(stand > 0.5, "standing on 20 won {}", stand);
+    assert!(hit < stand);
+}
+
+/// The EV table has a row for every hard total, and a best move for every cell
+#[rstest]
+fn ev_table_grid() {
+    let report = ev_table(Rules::default(), 10, &mut StdRng::seed_from_u64(3));
+    let rows = CHART_MAX_TOTAL - CHART_MIN_TOTAL + 1;
+    assert!(report.starts_with("Hit\n"));
+    assert!(report.contains("\nStand\n"));
+    assert!(report.contains("\nDouble Down\n"));
+    let best = report
+        .split("Best move (* differs from the probability table)\n")
+        .nth(1)
+        .unwrap();
+    // The header, then a row per total
+    assert_eq!(best.lines().count(), rows + 1);
+    assert!(best.lines().nth(1).unwrap().starts_with("  5"));
+}
+
+/// Every betting strategy gets an aligned row, simulated from the same seeds
+#[rstest]
+fn compare_betting_table() {
+    let config = SimConfig {
+        max_games: 20,
+        ..SimConfig::default()
+    };
+    let seeds = [1, 2, 3];
+    let table = compare_betting(&config, &seeds);
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), BettingStrategy::iter().count() + 1);
+    assert!(lines.iter().all(|line| line.len() == lines[0].len()));
+    let flat = simulate(&config, &seeds);
+    assert!(lines[1].starts_with("flat"));
+    assert!(lines[1].contains(&format!("${:.2}", flat.avg_credits())));
+    assert!(lines[4].starts_with("monte-carlo"));
+}
@@ -0,0 +1,439 @@
+//!
+//! File:           betting.rs
+//! Description:    Bet sizing strategies
+//!
+
+use rstest::rstest;
+use std::fmt;
+use std::slice::Iter;
+use std::str::FromStr;
+
+use crate::types::stats::SessionEnd;
+
+/// Wins in a row after which a Paroli bettor goes back to the base bet
+pub const PAROLI_MAX_WINS: usize = 3;
+/// Sequence (in units of the base bet) a Monte Carlo bettor starts each cycle with
+const MONTE_CARLO_SEQUENCE: [isize; 3] = [1, 2, 3];
+
+/// How the bet changes from one round to the next, based on the previous round's result
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BettingStrategy {
+    /// Always bet the base bet
+    Flat,
+    /// Double the bet after a loss, go back to the base bet after a win
+    Martingale,
+    /// Double the bet after a win, go back to the base bet after a loss or `PAROLI_MAX_WINS` wins
+    Paroli,
+    /// Bet the sum of the first and last numbers of a sequence. A win crosses both off, a loss
+    /// appends the lost bet. The cycle starts over once the sequence is crossed off.
+    MonteCarlo,
+    /// Bet the next Fibonacci number (in units of the base bet) after a loss, go back two numbers
+    /// after a win
+    Fibonacci,
+}
+
+impl BettingStrategy {
+    /// Iterator for traversing every betting strategy
+    pub fn iter() -> Iter<'static, BettingStrategy> {
+        static STRATEGIES: [BettingStrategy; 5] = [
+            BettingStrategy::Flat,
+            BettingStrategy::Martingale,
+            BettingStrategy::Paroli,
+            BettingStrategy::MonteCarlo,
+            BettingStrategy::Fibonacci,
+        ];
+        STRATEGIES.iter()
+    }
+}
+
+impl fmt::Display for BettingStrategy {
+    /// Renders the name the strategy is parsed from
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            BettingStrategy::Flat => "flat",
+            BettingStrategy::Martingale => "martingale",
+            BettingStrategy::Paroli => "paroli",
+            BettingStrategy::MonteCarlo => "monte-carlo",
+            BettingStrategy::Fibonacci => "fibonacci",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for BettingStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "flat" => Ok(BettingStrategy::Flat),
+            "martingale" => Ok(BettingStrategy::Martingale),
+            "paroli" => Ok(BettingStrategy::Paroli),
+            "monte-carlo" => Ok(BettingStrategy::MonteCarlo),
+            "fibonacci" => Ok(BettingStrategy::Fibonacci),
+            _ => Err(format!(
+                "`{}` is not a betting strategy (flat, martingale, paroli, monte-carlo or fibonacci)",
+                s
+            )),
+        }
+    }
+}
+
+/// Smallest and (optionally) largest bets a table accepts
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TableLimits {
+    pub min: isize,
+    pub max: Option<isize>,
+}
+
+impl TableLimits {
+    pub const fn new(min: isize, max: Option<isize>) -> Self {
+        TableLimits { min, max }
+    }
+
+    /// Clamps the `bet` a player wants to the table's limits. Returns the bet, and true if it had to be
+    /// lowered to the table maximum.
+    pub fn clamp(&self, bet: isize) -> (isize, bool) {
+        let bet = bet.max(self.min);
+        match self.max {
+            Some(max) if bet > max => (max, true),
+            _ => (bet, false),
+        }
+    }
+
+    /// Checks a bet placed with `credits` left. The bet has to fit the credits and the table's limits,
+    /// except that a player short of the minimum may bet all of their credits.
+    pub fn check(&self, bet: isize, credits: isize) -> Result<(), String> {
+        if bet <= 0 || bet > credits {
+            return Err(format!(
+                "the bet must be between $1 and your credits (${})",
+                credits
+            ));
+        }
+        if bet < self.min && bet != credits {
+            return Err(format!("the table minimum is ${}", self.min));
+        }
+        match self.max {
+            Some(max) if bet > max => Err(format!("the table maximum is ${}", max)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for TableLimits {
+    /// A table without a maximum, that takes bets of $1 and up
+    fn default() -> Self {
+        TableLimits::new(1, None)
+    }
+}
+
+/// Walk-away rules: a player leaves the table once their credits reach the `target`, or fall to the
+/// `stop_loss`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WalkAway {
+    pub target: Option<isize>,
+    pub stop_loss: Option<isize>,
+}
+
+impl WalkAway {
+    /// Returns how the session ends if the player walks away with `credits`, or `None` to keep playing
+    pub fn check(&self, credits: isize) -> Option<SessionEnd> {
+        if self.target.is_some_and(|target| credits >= target) {
+            return Some(SessionEnd::Target);
+        }
+        if self.stop_loss.is_some_and(|stop_loss| credits <= stop_loss) {
+            return Some(SessionEnd::StopLoss);
+        }
+        None
+    }
+}
+
+/// Sizes a player's bets over a run, following a `BettingStrategy`
+pub struct Bettor {
+    strategy: BettingStrategy,
+    base_bet: isize,
+    /// Bet the strategy wants for the next round, before limiting it to the credits available
+    next_bet: isize,
+    /// Rounds won in a row
+    win_streak: usize,
+    /// Monte Carlo sequence, in units of the base bet
+    sequence: Vec<isize>,
+    /// Position in the Fibonacci sequence, starting from the first 1
+    fibonacci_step: usize,
+}
+
+impl Bettor {
+    pub fn new(strategy: BettingStrategy, base_bet: isize) -> Self {
+        let mut bettor = Bettor {
+            strategy,
+            base_bet,
+            next_bet: base_bet,
+            win_streak: 0,
+            sequence: Vec::new(),
+            fibonacci_step: 0,
+        };
+        if strategy == BettingStrategy::MonteCarlo {
+            bettor.restart_sequence();
+        }
+        bettor
+    }
+
+    /// Length of the Monte Carlo sequence (0 for the other strategies)
+    pub fn sequence_len(&self) -> usize {
+        self.sequence.len()
+    }
+
+    /// Starts a new Monte Carlo cycle
+    fn restart_sequence(&mut self) {
+        self.sequence = MONTE_CARLO_SEQUENCE.to_vec();
+        self.next_bet = self.sequence_bet();
+    }
+
+    /// Monte Carlo bet: the first and last numbers of the sequence (or the only one left)
+    fn sequence_bet(&self) -> isize {
+        let units = match self.sequence[..] {
+            [only] => only,
+            [first, .., last] => first + last,
+            [] => MONTE_CARLO_SEQUENCE[0],
+        };
+        units * self.base_bet
+    }
+
+    /// Crosses off (after a win) or appends to (after a loss) the Monte Carlo sequence
+    fn update_sequence(&mut self, net: isize) {
+        if net > 0 {
+            self.sequence.pop();
+            if !self.sequence.is_empty() {
+                self.sequence.remove(0);
+            }
+            if self.sequence.is_empty() {
+                self.restart_sequence();
+                return;
+            }
+        } else if net < 0 {
+            let lost_units = self.next_bet / self.base_bet;
+            self.sequence.push(lost_units);
+        }
+        self.next_bet = self.sequence_bet();
+    }
+
+    /// Moves forward one Fibonacci number after a loss, or back two after a win
+    fn update_fibonacci(&mut self, net: isize) {
+        if net > 0 {
+            self.fibonacci_step = self.fibonacci_step.saturating_sub(2);
+        } else if net < 0 {
+            self.fibonacci_step += 1;
+        }
+        self.next_bet = fibonacci(self.fibonacci_step) * self.base_bet;
+    }
+
+    /// Bet the strategy wants for the next round, before any limits
+    pub fn wanted_bet(&self) -> isize {
+        self.next_bet
+    }
+
+    /// Returns the bet for the next round, and true if it had to be capped to the `credits` available
+    pub fn bet(&self, credits: isize) -> (isize, bool) {
+        (self.next_bet.min(credits), self.next_bet > credits)
+    }
+
+    /// Adjusts the next bet after a round that won (`net` > 0), lost (`net` < 0) or pushed credits
+    pub fn record_round(&mut self, net: isize) {
+        self.win_streak = if net > 0 { self.win_streak + 1 } else { 0 };
+        match self.strategy {
+            BettingStrategy::Flat => (),
+            BettingStrategy::MonteCarlo => self.update_sequence(net),
+            BettingStrategy::Fibonacci => self.update_fibonacci(net),
+            BettingStrategy::Martingale if net < 0 => self.next_bet *= 2,
+            BettingStrategy::Martingale if net > 0 => self.next_bet = self.base_bet,
+            BettingStrategy::Paroli if net > 0 && self.win_streak < PAROLI_MAX_WINS => {
+                self.next_bet *= 2
+            }
+            BettingStrategy::Paroli if net != 0 => {
+                self.next_bet = self.base_bet;
+                self.win_streak = 0;
+            }
+            // A push leaves the bet as is
+            _ => (),
+        }
+    }
+}
+
+/// Fibonacci number at `step` of the sequence 1, 1, 2, 3, 5, 8, ...
+fn fibonacci(step: usize) -> isize {
+    let (mut current, mut next) = (1, 1);
+    for _ in 0..step {
+        (current, next) = (next, current + next);
+    }
+    current
+}
+
+/// "Bold play" bet sizing: wager whatever most directly reaches the `target` in the fewest hands.
+/// The bet is the amount still needed to hit the target, capped by the available credits and the
+/// table maximum. Returns 0 once the target has been reached.
+pub fn bold_bet(credits: isize, target: isize, table_max: isize) -> isize {
+    let needed = target - credits;
+    if needed <= 0 || credits <= 0 {
+        return 0;
+    }
+    needed.min(credits).min(table_max)
+}
+
+/// Validates bold play bet sizing near and far from the goal
+#[rstest]
+// Close to the target, bet exactly what is needed
+#[case(90, 100, 50, 10)]
+// Far from the target, go all-in
+#[case(30, 100, 50, 30)]
+// The table maximum caps the bet
+#[case(60, 200, 50, 50)]
+// Target already reached or bankroll exhausted
+#[case(100, 100, 50, 0)]
+#[case(0, 100, 50, 0)]
+fn check_bold_bet(
+    #[case] credits: isize,
+    #[case] target: isize,
+    #[case] table_max: isize,
+    #[case] expected: isize,
+) {
+    assert_eq!(bold_bet(credits, target, table_max), expected)
+}
+
+/// Bet progressions after a sequence of round results
+#[rstest]
+#[case(BettingStrategy::Flat, &[-1, -1, 1], 5)]
+#[case(BettingStrategy::Martingale, &[-5, -10], 20)]
+#[case(BettingStrategy::Martingale, &[-5, -10, 0], 20)]
+#[case(BettingStrategy::Martingale, &[-5, -10, 20], 5)]
+#[case(BettingStrategy::Paroli, &[5, 10], 20)]
+#[case(BettingStrategy::Paroli, &[5, 10, 20], 5)]
+#[case(BettingStrategy::Paroli, &[5, -10], 5)]
+fn check_bet_progression(
+    #[case] strategy: BettingStrategy,
+    #[case] results: &[isize],
+    #[case] expected: isize,
+) {
+    let mut bettor = Bettor::new(strategy, 5);
+    for net in results {
+        bettor.record_round(*net);
+    }
+    assert_eq!(bettor.bet(1000), (expected, false));
+}
+
+/// Fibonacci bets step forward after a loss and back two steps after a win
+#[rstest]
+#[case(&[], 5)]
+#[case(&[-5], 5)]
+#[case(&[-5, -5, -10, -15], 25)]
+#[case(&[-5, -5, -10, -15, 25], 10)]
+#[case(&[-5, 5], 5)]
+// A push keeps the bet
+#[case(&[-5, -5, 0], 10)]
+fn check_fibonacci(#[case] results: &[isize], #[case] expected: isize) {
+    let mut bettor = Bettor::new(BettingStrategy::Fibonacci, 5);
+    for net in results {
+        bettor.record_round(*net);
+    }
+    assert_eq!(bettor.bet(1000), (expected, false));
+    assert_eq!(bettor.bet(12), (expected.min(12), expected > 12));
+}
+
+/// Players walk away at their target or stop-loss, and keep playing in between
+#[rstest]
+#[case(200, Some(SessionEnd::Target))]
+#[case(250, Some(SessionEnd::Target))]
+#[case(199, None)]
+#[case(51, None)]
+#[case(50, Some(SessionEnd::StopLoss))]
+fn check_walk_away(#[case] credits: isize, #[case] expected: Option<SessionEnd>) {
+    let walk_away = WalkAway {
+        target: Some(200),
+        stop_loss: Some(50),
+    };
+    assert_eq!(walk_away.check(credits), expected);
+    assert_eq!(WalkAway::default().check(credits), None);
+}
+
+/// Bets are clamped to the table's limits
+#[rstest]
+#[case(TableLimits::new(5, Some(100)), 1, (5, false))]
+#[case(TableLimits::new(5, Some(100)), 50, (50, false))]
+#[case(TableLimits::new(5, Some(100)), 100, (100, false))]
+#[case(TableLimits::new(5, Some(100)), 160, (100, true))]
+#[case(TableLimits::new(5, None), 1_000_000, (1_000_000, false))]
+fn check_table_limits(
+    #[case] limits: TableLimits,
+    #[case] wanted: isize,
+    #[case] expected: (isize, bool),
+) {
+    assert_eq!(limits.clamp(wanted), expected);
+}
+
+/// Human bets have to fit the table's limits and the credits left
+#[rstest]
+#[case(10, 200, true)]
+#[case(4, 200, false)]
+#[case(101, 200, false)]
+#[case(50, 40, false)]
+#[case(0, 200, false)]
+// Short of the minimum, a player may go all in
+#[case(3, 3, true)]
+fn check_table_bet(#[case] bet: isize, #[case] credits: isize, #[case] valid: bool) {
+    let limits = TableLimits::new(5, Some(100));
+    assert_eq!(limits.check(bet, credits).is_ok(), valid);
+}
+
+/// A bet larger than the credits available is capped
+#[rstest]
+fn bet_capped_by_credits() {
+    let mut bettor = Bettor::new(BettingStrategy::Martingale, 5);
+    bettor.record_round(-5);
+    bettor.record_round(-10);
+    assert_eq!(bettor.bet(15), (15, true));
+    assert_eq!(bettor.bet(20), (20, false));
+}
+
+/// Betting strategies parse from their names
+#[rstest]
+#[case("flat", Ok(BettingStrategy::Flat))]
+#[case("Martingale", Ok(BettingStrategy::Martingale))]
+#[case("paroli", Ok(BettingStrategy::Paroli))]
+#[case("labouchere", Err(()))]
+fn check_betting_strategy_from_str(#[case] s: &str, #[case] expected: Result<BettingStrategy, ()>) {
+    assert_eq!(s.parse::<BettingStrategy>().map_err(|_| ()), expected);
+}
+
+/// Monte Carlo bets follow the sequence, which starts over once it is crossed off
+#[rstest]
+// Bet 1 + 3
+#[case(&[], 20, 3)]
+// Lose 4: [1, 2, 3, 4], bet 1 + 4
+#[case(&[-20], 25, 4)]
+// Win: [2, 3], bet 2 + 3
+#[case(&[-20, 25], 25, 2)]
+// Win: [], start over
+#[case(&[-20, 25, 25], 20, 3)]
+// Pushes don't change the sequence
+#[case(&[-20, 0], 25, 4)]
+// Lose twice: [1, 2, 3, 4, 5], win twice: [3], bet 3
+#[case(&[-20, -25, 30, 30], 15, 1)]
+fn check_monte_carlo(
+    #[case] results: &[isize],
+    #[case] expected_bet: isize,
+    #[case] expected_len: usize,
+) {
+    let mut bettor = Bettor::new(BettingStrategy::MonteCarlo, 5);
+    for net in results {
+        bettor.record_round(*net);
+    }
+    assert_eq!(bettor.bet(1000), (expected_bet, false));
+    assert_eq!(bettor.sequence_len(), expected_len);
+}
+
+/// Every strategy parses back from its name
+#[rstest]
+fn betting_strategy_names() {
+    assert_eq!(BettingStrategy::iter().count(), 5);
+    for strategy in BettingStrategy::iter() {
+        assert_eq!(strategy.to_string().parse(), Ok(*strategy));
+    }
+}
@@ -6,11 +6,30 @@
 use rstest::rstest;
 use std::fmt;
 use std::slice::Iter;
+use std::str::FromStr;
 
 pub const MAX_BLACKJACK: usize = 21;
 
+/// ANSI escape code that colors the red suits
+const ANSI_RED: &str = "\x1b[31m";
+/// ANSI escape code that colors the black suits in the terminal's default color, since black text
+/// disappears on a dark background
+const ANSI_DEFAULT_FG: &str = "\x1b[39m";
+/// ANSI escape code that restores the terminal's color
+const ANSI_RESET: &str = "\x1b[0m";
+
+/// Describes how cards are rendered
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CardStyle {
+    /// Plain text, i.e. "Ace of Spades"
+    #[default]
+    Plain,
+    /// Unicode suit symbols, in red or black with ANSI color codes, i.e. "Ace ♠"
+    Color,
+}
+
 /// Enumeration representing the "type" of a card
-#[derive(Clone, Copy)]
+#[derive(Hash, PartialEq, Eq, Debug, Clone, Copy)]
 pub enum Suit {
     Hearts,
     Diamonds,
@@ -24,6 +43,32 @@ impl Suit {
         static SUITS: [Suit; 4] = [Suit::Hearts, Suit::Diamonds, Suit::Spades, Suit::Clubs];
         SUITS.iter()
     }
+
+    /// Unicode symbol of the suit
+    pub fn symbol(&self) -> char {
+        match *self {
+            Suit::Hearts => '♥',
+            Suit::Diamonds => '♦',
+            Suit::Spades => '♠',
+            Suit::Clubs => '♣',
+        }
+    }
+
+    /// Returns true for Hearts and Diamonds
+    pub fn is_red(&self) -> bool {
+        matches!(*self, Suit::Hearts | Suit::Diamonds)
+    }
+
+    /// Returns the Suit named by its first letter (i.e. 'H' for Hearts), in either case
+    pub fn from_char(c: char) -> Option<Suit> {
+        match c.to_ascii_uppercase() {
+            'H' => Some(Suit::Hearts),
+            'D' => Some(Suit::Diamonds),
+            'S' => Some(Suit::Spades),
+            'C' => Some(Suit::Clubs),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for Suit {
@@ -38,8 +83,9 @@ impl fmt::Display for Suit {
     }
 }
 
-/// Enumeration representing the "value" of a card
-#[derive(Hash, PartialEq, Eq, Debug, Clone, Copy)]
+/// Enumeration representing the "value" of a card. Ranks are ordered from Two (lowest) to Ace
+/// (highest), with the face cards above the Ten.
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
 pub enum Rank {
     Two,
     Three,
@@ -95,6 +141,46 @@ impl Rank {
         ];
         RANKS.iter()
     }
+
+    /// Returns true for the ranks worth 10: Ten, Jack, Queen and King
+    pub fn is_ten_value(&self) -> bool {
+        matches!(self, Rank::Ten | Rank::Jack | Rank::Queen | Rank::King)
+    }
+
+    /// Returns true for an Ace
+    pub fn is_ace(&self) -> bool {
+        *self == Rank::Ace
+    }
+
+    /// Returns the Rank with the numeric value `value`. A 10 is always a Ten and an Ace may be given as
+    /// either 1 or 11.
+    pub fn from_value(value: usize) -> Option<Rank> {
+        match value {
+            1 | 11 => Some(Rank::Ace),
+            10 => Some(Rank::Ten),
+            _ => Rank::iter().find(|rank| rank.value() == value).copied(),
+        }
+    }
+}
+
+impl FromStr for Rank {
+    type Err = String;
+
+    /// Parses "2" through "10", "J", "Q", "K" or "A", in either case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "J" => Some(Rank::Jack),
+            "Q" => Some(Rank::Queen),
+            "K" => Some(Rank::King),
+            "A" => Some(Rank::Ace),
+            num => num
+                .parse::<usize>()
+                .ok()
+                .filter(|value| (2..=10).contains(value))
+                .and_then(Rank::from_value),
+        }
+        .ok_or_else(|| format!("`{}` is not a card rank", s))
+    }
 }
 
 impl fmt::Display for Rank {
@@ -119,18 +205,58 @@ impl fmt::Display for Rank {
 }
 
 /// Represents a card in a deck
-#[derive(Clone, Copy)]
+#[derive(Hash, PartialEq, Eq, Debug, Clone, Copy)]
 pub struct Card {
     pub suit: Suit,
     pub rank: Rank,
 }
 
+impl Card {
+    /// Renders the card in `style`. The plain style is the same as `Display`.
+    pub fn styled(&self, style: CardStyle) -> String {
+        match style {
+            CardStyle::Plain => self.to_string(),
+            CardStyle::Color => {
+                let color = if self.suit.is_red() {
+                    ANSI_RED
+                } else {
+                    ANSI_DEFAULT_FG
+                };
+                format!(
+                    "{}{} {}{}",
+                    color,
+                    self.rank,
+                    self.suit.symbol(),
+                    ANSI_RESET
+                )
+            }
+        }
+    }
+}
+
 impl fmt::Display for Card {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{} of {}", self.rank, self.suit)
     }
 }
 
+impl FromStr for Card {
+    type Err = String;
+
+    /// Parses a rank followed by the first letter of a suit, i.e. "AS", "10h" or "7D"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let mut chars = s.chars();
+        let suit = chars
+            .next_back()
+            .ok_or_else(|| String::from("a card can't be empty"))?;
+        let suit = Suit::from_char(suit)
+            .ok_or_else(|| format!("`{}` is not a suit (H, D, S or C)", suit))?;
+        let rank = chars.as_str().parse::<Rank>()?;
+        Ok(Card { suit, rank })
+    }
+}
+
 /// Validates card serialization
 #[rstest]
 #[case(Card{suit: Suit::Spades, rank: Rank::Ace}, "Ace of Spades")]
@@ -140,3 +266,99 @@ impl fmt::Display for Card {
 fn display_cards(#[case] card: Card, #[case] expected: &str) {
     assert_eq!(card.to_string(), expected)
 }
+
+/// Colored cards use suit symbols, while plain cards render as before
+#[rstest]
+#[case(Card{suit: Suit::Hearts, rank: Rank::Ace}, "\x1b[31mAce ♥\x1b[0m")]
+#[case(Card{suit: Suit::Diamonds, rank: Rank::Ten}, "\x1b[31m10 ♦\x1b[0m")]
+#[case(Card{suit: Suit::Spades, rank: Rank::Queen}, "\x1b[39mQueen ♠\x1b[0m")]
+#[case(Card{suit: Suit::Clubs, rank: Rank::Two}, "\x1b[39m2 ♣\x1b[0m")]
+fn styled_cards(#[case] card: Card, #[case] expected: &str) {
+    assert_eq!(card.styled(CardStyle::Color), expected);
+    assert_eq!(card.styled(CardStyle::Plain), card.to_string());
+}
+
+/// Ranks are ordered from Two up to Ace, consistently with their values
+#[rstest]
+fn check_rank_order() {
+    let ranks: Vec<&Rank> = Rank::iter().collect();
+    for pair in ranks.windows(2) {
+        assert!(pair[0] < pair[1]);
+        assert!(pair[0].value() <= pair[1].value());
+    }
+    assert!(Rank::King > Rank::Ten);
+    assert_eq!(Rank::King.value(), Rank::Ten.value());
+    assert_ne!(Rank::King, Rank::Ten);
+    assert_eq!(Rank::iter().max(), Some(&Rank::Ace));
+}
+
+/// Ranks can be looked up by their value
+#[rstest]
+#[case(2, Some(Rank::Two))]
+#[case(9, Some(Rank::Nine))]
+#[case(10, Some(Rank::Ten))]
+#[case(11, Some(Rank::Ace))]
+#[case(1, Some(Rank::Ace))]
+#[case(0, None)]
+#[case(12, None)]
+fn check_rank_from_value(#[case] value: usize, #[case] expected: Option<Rank>) {
+    assert_eq!(Rank::from_value(value), expected);
+}
+
+/// Only the Ten and the face cards are ten-valued, and only the Ace is an Ace
+#[rstest]
+fn check_ten_values_and_aces() {
+    let ten_values: Vec<Rank> = Rank::iter().filter(|r| r.is_ten_value()).copied().collect();
+    assert_eq!(
+        ten_values,
+        vec![Rank::Ten, Rank::Jack, Rank::Queen, Rank::King]
+    );
+    let aces: Vec<Rank> = Rank::iter().filter(|r| r.is_ace()).copied().collect();
+    assert_eq!(aces, vec![Rank::Ace]);
+}
+
+/// Ranks parse from their short names
+#[rstest]
+#[case("2", Ok(Rank::Two))]
+#[case("10", Ok(Rank::Ten))]
+#[case("j", Ok(Rank::Jack))]
+#[case("Q", Ok(Rank::Queen))]
+#[case("k", Ok(Rank::King))]
+#[case("A", Ok(Rank::Ace))]
+#[case("1", Err(()))]
+#[case("11", Err(()))]
+#[case("Z", Err(()))]
+#[case("", Err(()))]
+fn check_rank_from_str(#[case] s: &str, #[case] expected: Result<Rank, ()>) {
+    assert_eq!(s.parse::<Rank>().map_err(|_| ()), expected);
+}
+
+/// Suits parse from their first letter
+#[rstest]
+#[case('H', "Hearts")]
+#[case('d', "Diamonds")]
+#[case('S', "Spades")]
+#[case('c', "Clubs")]
+fn check_suit_from_char(#[case] c: char, #[case] expected: &str) {
+    assert_eq!(Suit::from_char(c).unwrap().to_string(), expected);
+}
+
+/// Unknown suits don't parse
+#[rstest]
+fn check_suit_from_char_invalid() {
+    assert!(Suit::from_char('X').is_none());
+}
+
+/// Cards parse from a rank followed by a suit letter
+#[rstest]
+#[case("AS", Ok(Card{suit: Suit::Spades, rank: Rank::Ace}))]
+#[case("10h", Ok(Card{suit: Suit::Hearts, rank: Rank::Ten}))]
+#[case("7D", Ok(Card{suit: Suit::Diamonds, rank: Rank::Seven}))]
+#[case("qc", Ok(Card{suit: Suit::Clubs, rank: Rank::Queen}))]
+#[case("AX", Err(String::from("`X` is not a suit (H, D, S or C)")))]
+#[case("1S", Err(String::from("`1` is not a card rank")))]
+#[case("S", Err(String::from("`` is not a card rank")))]
+#[case("", Err(String::from("a card can't be empty")))]
+fn check_card_from_str(#[case] s: &str, #[case] expected: Result<Card, String>) {
+    assert_eq!(s.parse::<Card>(), expected);
+}
@@ -0,0 +1,128 @@
+//!
+//! File:           counting.rs
+//! Description:    Hi-Lo card counting, which persists across rounds dealt from the same shoe
+//!
+use rstest::rstest;
+
+#[cfg(test)]
+use crate::types::card::Suit;
+use crate::types::card::{Card, Rank};
+use crate::types::deck::Deck;
+
+/// Cards per standard deck, used to convert the cards left into decks left
+const CARDS_PER_DECK: f64 = 52.0;
+/// Fewest decks assumed left in the shoe, so the true count doesn't blow up at the end of a shoe
+const MIN_DECKS_REMAINING: f64 = 0.5;
+/// Largest bet, in units of the base bet, that the counter will place
+pub const MAX_BET_SPREAD: isize = 8;
+
+/// Hi-Lo value of a card: +1 for 2 through 6, 0 for 7 through 9, -1 for 10 through Ace
+pub fn hi_lo_value(rank: Rank) -> isize {
+    match rank.value() {
+        2..=6 => 1,
+        7..=9 => 0,
+        _ => -1,
+    }
+}
+
+/// Hi-Lo running count of every card seen since the shoe was shuffled
+pub struct HiLoCounter {
+    running_count: isize,
+    /// Number of the shoe's dealt cards that have been counted
+    cards_counted: usize,
+}
+
+impl HiLoCounter {
+    /// Constructs a counter for a freshly shuffled shoe
+    pub fn new() -> Self {
+        HiLoCounter {
+            running_count: 0,
+            cards_counted: 0,
+        }
+    }
+
+    /// Starts the count over, for when the shoe is reshuffled
+    pub fn reset(&mut self) {
+        self.running_count = 0;
+        self.cards_counted = 0;
+    }
+
+    /// Counts every card dealt from `deck` that hasn't been counted yet, starting over if `deck` was
+    /// replaced. Only call this once every dealt card is face-up, i.e. between rounds.
+    pub fn count_dealt(&mut self, deck: &Deck) {
+        let dealt = deck.dealt_cards();
+        // A deck that ran out mid-round is replaced by a fresh one
+        if dealt.len() < self.cards_counted {
+            self.reset();
+        }
+        for card in dealt[self.cards_counted..].iter() {
+            self.running_count += hi_lo_value(card.rank);
+        }
+        self.cards_counted = dealt.len();
+    }
+
+    /// Running count after also counting the `visible` cards of the current round
+    pub fn running_count_with(&self, visible: &[Card]) -> isize {
+        self.running_count + visible.iter().map(|c| hi_lo_value(c.rank)).sum::<isize>()
+    }
+
+    /// Running count divided by the number of decks left in `deck`
+    pub fn true_count(&self, deck: &Deck, visible: &[Card]) -> f64 {
+        let decks_remaining = (deck.remaining() as f64 / CARDS_PER_DECK).max(MIN_DECKS_REMAINING);
+        self.running_count_with(visible) as f64 / decks_remaining
+    }
+
+    /// Bet for the next round: 1 `base_bet` per point of true count (at least 1, at most
+    /// `MAX_BET_SPREAD`), limited to the credits available.
+    pub fn bet(&self, deck: &Deck, base_bet: isize, credits: isize) -> isize {
+        let units = (self.true_count(deck, &[]).floor() as isize).clamp(1, MAX_BET_SPREAD);
+        (units * base_bet).min(credits)
+    }
+}
+
+impl Default for HiLoCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Low cards raise the count, high cards lower it
+#[rstest]
+fn check_running_count() {
+    let mut deck = Deck::new();
+    let mut counter = HiLoCounter::new();
+    let mut expected = 0;
+    for _ in 0..10 {
+        expected += hi_lo_value(deck.deal().unwrap().rank);
+    }
+    counter.count_dealt(&deck);
+    assert_eq!(counter.running_count_with(&[]), expected);
+
+    // Counting again doesn't double count
+    counter.count_dealt(&deck);
+    let visible = [Card {
+        suit: Suit::Hearts,
+        rank: Rank::Five,
+    }];
+    assert_eq!(counter.running_count_with(&visible), expected + 1);
+}
+
+/// Bets ramp up with the true count
+#[rstest]
+#[case(0, 1)]
+#[case(-5, 1)]
+#[case(6, 3)]
+#[case(100, MAX_BET_SPREAD)]
+fn check_counter_bet(#[case] running_count: isize, #[case] expected_units: isize) {
+    // 2 decks left in the shoe
+    let mut deck = Deck::with_decks(3);
+    for _ in 0..52 {
+        deck.deal();
+    }
+    let counter = HiLoCounter {
+        running_count,
+        cards_counted: 52,
+    };
+    assert_eq!(counter.bet(&deck, 5, 1000), 5 * expected_units);
+    assert_eq!(counter.bet(&deck, 5, 4), 4);
+}
@@ -3,9 +3,11 @@
 //! Description:    Describes a deck of cards
 //!
 
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::{thread_rng, Rng, SeedableRng};
 use rstest::{fixture, rstest};
+use std::collections::HashMap;
 use std::fmt;
 
 use crate::types::card::Card;
@@ -13,38 +15,284 @@ use crate::types::card::Rank;
 use crate::types::card::Suit;
 
 const SIZE_OF_DECK: usize = 52;
+/// Number of cards in the opening deal: 2 for the player, then the dealer's down and up cards
+pub const OPENING_DEAL_CARDS: usize = 4;
+/// Smallest number of decks that can make up a shoe
+const MIN_DECKS: usize = 1;
 
-/// Represents a virtual deck of cards
+/// Error returned when a card is drawn from a deck that has run out of cards
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeckEmpty;
+
+impl fmt::Display for DeckEmpty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Deck ran out of cards!")
+    }
+}
+
+impl std::error::Error for DeckEmpty {}
+
+/// Represents a virtual deck of cards. May be a "shoe" of several decks.
+#[derive(Clone)]
 pub struct Deck {
     cards: Vec<Card>,
+    /// Cards that have been dealt, in the order they were dealt
+    dealt: Vec<Card>,
+    /// Number of cards burned (discarded unseen) instead of being dealt
+    burned: usize,
+    num_decks: usize,
 }
 
 impl Deck {
     /// Constructs a new deck, containing all 52 cards, shuffled
     pub fn new() -> Self {
+        Deck::with_decks(1)
+    }
+
+    /// Constructs a shoe of `num_decks` standard decks, shuffled together. A shoe must have at least
+    /// 1 deck, so a count of 0 is clamped to 1 (with a warning).
+    pub fn with_decks(num_decks: usize) -> Self {
+        let mut deck = Deck::unshuffled(num_decks);
+        deck.shuffle();
+        deck
+    }
+
+    /// Constructs a single deck shuffled from `seed`. The same seed always produces the same deck.
+    pub fn new_seeded(seed: u64) -> Self {
+        Deck::with_rng(MIN_DECKS, &mut StdRng::seed_from_u64(seed))
+    }
+
+    /// Constructs a shoe of `num_decks` standard decks, shuffled by the provided random number
+    /// generator. A seeded generator produces a reproducible shoe. Deck counts are validated like
+    /// `with_decks()`.
+    pub fn with_rng<R: Rng + ?Sized>(num_decks: usize, rng: &mut R) -> Self {
+        let mut deck = Deck::unshuffled(num_decks);
+        deck.shuffle_with(rng);
+        deck
+    }
+
+    /// Constructs a shoe of `num_decks` standard decks where the opening deal is fixed by `round`, and
+    /// the rest of the shoe is shuffled by `rng`. Every deck built for the same round deals the same
+    /// starting hands, so strategies can be compared on identical deals even after their draws
+    /// diverge.
+    pub fn with_fixed_deal<R: Rng + ?Sized>(num_decks: usize, round: u64, rng: &mut R) -> Self {
+        let mut deck = Deck::with_rng(num_decks, &mut StdRng::seed_from_u64(round));
+        // Cards are dealt from the end of the deck
+        let opening = deck.cards.split_off(deck.cards.len() - OPENING_DEAL_CARDS);
+        deck.shuffle_with(rng);
+        deck.cards.extend(opening);
+        deck
+    }
+
+    /// Constructs a deck of exactly `cards`, which is never shuffled. Cards are dealt from the end of
+    /// the vector, so the last card is dealt first. Used to script deals.
+    pub fn from_cards(cards: Vec<Card>) -> Self {
+        Deck {
+            num_decks: cards.len().div_ceil(SIZE_OF_DECK).max(MIN_DECKS),
+            dealt: Vec::with_capacity(cards.len()),
+            burned: 0,
+            cards,
+        }
+    }
+
+    /// Constructs a deck from a scenario: whitespace separated cards (i.e. "AS KH 7D"), dealt in the order
+    /// they are listed. Errors name the line and the card that failed to parse.
+    pub fn from_scenario(scenario: &str) -> Result<Self, String> {
+        let mut cards = Vec::new();
+        for (line_num, line) in scenario.lines().enumerate() {
+            for token in line.split_whitespace() {
+                let card = token.parse::<Card>().map_err(|err| {
+                    format!(
+                        "line {}, card {} (`{}`): {}",
+                        line_num + 1,
+                        cards.len() + 1,
+                        token,
+                        err
+                    )
+                })?;
+                cards.push(card);
+            }
+        }
+        if cards.is_empty() {
+            return Err(String::from("the scenario has no cards"));
+        }
+        // The last card is dealt first
+        cards.reverse();
+        Ok(Deck::from_cards(cards))
+    }
+
+    /// Constructs a shoe of `num_decks` standard decks, in order
+    fn unshuffled(num_decks: usize) -> Self {
+        let num_decks = if num_decks < MIN_DECKS {
+            eprintln!(
+                "Warning: a shoe needs at least {} deck, using {} instead of {}.",
+                MIN_DECKS, MIN_DECKS, num_decks
+            );
+            MIN_DECKS
+        } else {
+            num_decks
+        };
         let mut deck = Deck {
-            cards: Vec::with_capacity(SIZE_OF_DECK),
+            cards: Vec::with_capacity(num_decks * SIZE_OF_DECK),
+            dealt: Vec::with_capacity(num_decks * SIZE_OF_DECK),
+            burned: 0,
+            num_decks,
         };
+        deck.refill();
+        deck
+    }
 
-        for s in Suit::iter() {
-            for r in Rank::iter() {
-                deck.cards.push(Card { suit: *s, rank: *r });
+    /// Puts every card of the shoe back, in order, reusing the existing allocations
+    fn refill(&mut self) {
+        self.cards.clear();
+        self.dealt.clear();
+        self.burned = 0;
+        for _ in 0..self.num_decks {
+            for s in Suit::iter() {
+                for r in Rank::iter() {
+                    self.cards.push(Card { suit: *s, rank: *r });
+                }
             }
         }
-        deck.shuffle();
+    }
 
-        deck
+    /// Returns every card to the shoe and shuffles it, without reallocating. The result is a full,
+    /// standard shoe of the same number of decks, even if this deck was built from a scenario.
+    pub fn reset(&mut self) {
+        self.reset_with(&mut thread_rng());
+    }
+
+    /// Like `reset()`, but shuffles using the provided random number generator. Resetting with a given
+    /// generator deals the same cards as `Deck::with_rng()` would with it.
+    pub fn reset_with<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.refill();
+        self.shuffle_with(rng);
+    }
+
+    /// Number of cards in the deck before any were dealt, i.e. the original size of a shoe
+    pub fn total(&self) -> usize {
+        self.cards.len() + self.dealt.len() + self.burned
+    }
+
+    /// Same as `total()`
+    pub fn total_cards(&self) -> usize {
+        self.total()
+    }
+
+    /// Number of standard decks that make up this deck
+    pub fn num_decks(&self) -> usize {
+        self.num_decks
+    }
+
+    /// Number of cards left to be dealt
+    pub fn remaining(&self) -> usize {
+        self.cards.len()
+    }
+
+    /// Cards dealt so far, in the order they were dealt
+    pub fn dealt_cards(&self) -> &[Card] {
+        &self.dealt
+    }
+
+    /// Returns true once the cut card is reached: at least `penetration` (a fraction between 0 and 1)
+    /// of the shoe has been dealt or burned.
+    pub fn needs_reshuffle(&self, penetration: f64) -> bool {
+        (self.dealt.len() + self.burned) as f64 >= penetration * self.total() as f64
+    }
+
+    /// Number of each rank left to be dealt
+    pub fn rank_counts(&self) -> HashMap<Rank, usize> {
+        let mut counts = HashMap::new();
+        for card in self.cards.iter() {
+            *counts.entry(card.rank).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Ratio of high cards (10 through Ace) to low cards (2 through 6) left to be dealt. Card counters
+    /// favor the player when this is above 1.
+    pub fn high_low_ratio(&self) -> f64 {
+        let (mut high, mut low) = (0, 0);
+        for card in self.cards.iter() {
+            match card.rank.value() {
+                2..=6 => low += 1,
+                10 | 11 => high += 1,
+                _ => (),
+            }
+        }
+        high as f64 / low as f64
+    }
+
+    /// Renders how many of each rank are left to be dealt, followed by the high/low ratio
+    pub fn composition(&self) -> String {
+        let counts = self.rank_counts();
+        let mut report = String::new();
+        for rank in Rank::iter() {
+            report.push_str(&format!(
+                "{:>5}: {}\n",
+                rank.to_string(),
+                counts.get(rank).copied().unwrap_or(0)
+            ));
+        }
+        report.push_str(&format!("High/low ratio: {:.2}", self.high_low_ratio()));
+        report
+    }
+
+    /// Returns a copy of this deck with the remaining cards in a random order. Used for Monte Carlo
+    /// rollouts that shouldn't assume the actual order of the cards that are left.
+    pub fn shuffled_copy_of_remaining<R: Rng + ?Sized>(&self, rng: &mut R) -> Deck {
+        let mut copy = self.clone();
+        copy.shuffle_with(rng);
+        copy
     }
 
     /// Randomly shuffles cards in a deck. According to the internet, most
     /// digital variants of card games shuffle on each hand.
     pub fn shuffle(&mut self) {
-        self.cards.shuffle(&mut thread_rng());
+        self.shuffle_with(&mut thread_rng());
+    }
+
+    /// Shuffles the cards left in the deck reproducibly, using `seed`
+    pub fn shuffle_seeded(&mut self, seed: u64) {
+        self.shuffle_with(&mut StdRng::seed_from_u64(seed));
+    }
+
+    /// Shuffles the cards left in the deck using the provided random number generator
+    pub fn shuffle_with<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.cards.shuffle(rng);
+    }
+
+    /// Burns (discards unseen) the next `n` cards, like a casino does after shuffling. Burned cards
+    /// are not dealt, so they can't be counted. Returns how many were burned, which is fewer than `n`
+    /// if the deck runs out.
+    pub fn burn(&mut self, n: usize) -> usize {
+        let burned = n.min(self.cards.len());
+        self.cards.truncate(self.cards.len() - burned);
+        self.burned += burned;
+        burned
+    }
+
+    /// Number of cards burned so far
+    pub fn burned(&self) -> usize {
+        self.burned
     }
 
     /// Deals 1 card
     pub fn deal(&mut self) -> Option<Card> {
-        self.cards.pop()
+        let card = self.cards.pop()?;
+        self.dealt.push(card);
+        Some(card)
+    }
+
+    /// Returns the next card to be dealt, without dealing it
+    pub fn peek(&self) -> Option<&Card> {
+        self.cards.last()
+    }
+}
+
+impl Default for Deck {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -77,3 +325,260 @@ fn deal_empty_deck(mut deck_fixture: Deck) {
     }
     assert!(deck_fixture.deal().is_none());
 }
+
+/// A shoe remembers its original size, even after cards are dealt
+#[rstest]
+fn shoe_sizes() {
+    let mut shoe = Deck::with_decks(6);
+    for _ in 0..10 {
+        shoe.deal();
+    }
+    assert_eq!(shoe.total(), 312);
+    assert_eq!(shoe.total_cards(), 312);
+    assert_eq!(shoe.num_decks(), 6);
+    assert_eq!(shoe.cards.len(), 302);
+}
+
+/// Every card dealt from a shoe of several decks is one less card remaining
+#[rstest]
+fn remaining_in_shoe() {
+    let mut shoe = Deck::with_decks(2);
+    assert_eq!(shoe.remaining(), shoe.total());
+    for dealt in 1..=shoe.total() {
+        shoe.deal();
+        assert_eq!(shoe.remaining(), 104 - dealt);
+    }
+    assert_eq!(shoe.deal(), None);
+    assert_eq!(shoe.remaining(), 0);
+    assert_eq!(shoe.total(), 104);
+}
+
+/// Burning discards cards from the top of the deck, without dealing them
+#[rstest]
+fn burn_cards(mut deck_fixture: Deck) {
+    let next = deck_fixture.cards[deck_fixture.cards.len() - 4];
+    assert_eq!(deck_fixture.burn(3), 3);
+    assert_eq!(deck_fixture.remaining(), 49);
+    assert_eq!(deck_fixture.burned(), 3);
+    assert!(deck_fixture.dealt_cards().is_empty());
+    assert_eq!(deck_fixture.total(), 52);
+    assert_eq!(deck_fixture.deal(), Some(next));
+
+    // A short deck burns what it has left, and an empty one burns nothing
+    assert_eq!(deck_fixture.burn(100), 48);
+    assert_eq!(deck_fixture.remaining(), 0);
+    assert_eq!(deck_fixture.burn(1), 0);
+    assert!(deck_fixture.needs_reshuffle(0.75));
+}
+
+/// Dealt cards are retained in deal order
+#[rstest]
+fn dealt_cards_in_order(mut deck_fixture: Deck) {
+    let mut expected = Vec::new();
+    for _ in 0..3 {
+        expected.push(deck_fixture.deal().unwrap());
+    }
+    assert_eq!(deck_fixture.dealt_cards(), expected);
+    assert_eq!(deck_fixture.remaining(), 49);
+}
+
+/// A deck built from cards deals them back from the end
+#[rstest]
+fn deck_from_cards() {
+    let mut deck = Deck::from_cards(vec![
+        Card {
+            suit: Suit::Hearts,
+            rank: Rank::Two,
+        },
+        Card {
+            suit: Suit::Spades,
+            rank: Rank::Ace,
+        },
+    ]);
+    assert_eq!(deck.num_decks(), 1);
+    assert_eq!(deck.total(), 2);
+    assert_eq!(
+        deck.deal(),
+        Some(Card {
+            suit: Suit::Spades,
+            rank: Rank::Ace
+        })
+    );
+    assert_eq!(
+        deck.deal(),
+        Some(Card {
+            suit: Suit::Hearts,
+            rank: Rank::Two
+        })
+    );
+    assert_eq!(deck.deal(), None);
+}
+
+/// A shoe holds every card exactly once per deck
+#[rstest]
+fn shoe_well_formed() {
+    let mut shoe = Deck::with_decks(4);
+    let mut frequencies: HashMap<Card, usize> = HashMap::new();
+    while let Some(card) = shoe.deal() {
+        *frequencies.entry(card).or_insert(0) += 1;
+    }
+    assert_eq!(frequencies.len(), 52);
+    assert!(frequencies.values().all(|count| *count == 4));
+}
+
+/// A reset deck is full again, reuses its allocation and deals like a freshly built one
+#[rstest]
+fn reset_refills_in_place() {
+    let mut deck = Deck::new_seeded(7);
+    deck.burn(1);
+    while deck.deal().is_some() {}
+    let capacity = deck.cards.capacity();
+    deck.reset_with(&mut StdRng::seed_from_u64(3));
+    assert_eq!(deck.cards.capacity(), capacity);
+    assert_eq!(deck.remaining(), 52);
+    assert_eq!(deck.burned(), 0);
+    let mut fresh = Deck::with_rng(1, &mut StdRng::seed_from_u64(3));
+    while let Some(card) = fresh.deal() {
+        assert_eq!(deck.deal(), Some(card));
+    }
+}
+
+/// Shuffling after a reset is still uniform: every card turns up on top about equally often
+#[rstest]
+fn reset_shuffle_is_uniform() {
+    const RESETS: usize = 52 * 200;
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut deck = Deck::with_rng(1, &mut rng);
+    let mut frequencies: HashMap<Card, usize> = HashMap::new();
+    for _ in 0..RESETS {
+        deck.reset_with(&mut rng);
+        *frequencies.entry(deck.deal().unwrap()).or_insert(0) += 1;
+    }
+    assert_eq!(frequencies.len(), 52);
+    // Expect 200 of each; allow a generous margin so the test isn't flaky across rand versions
+    assert!(frequencies
+        .values()
+        .all(|count| (140..=260).contains(count)));
+}
+
+/// An empty shoe is clamped to a single, usable deck
+#[rstest]
+fn zero_decks_clamped() {
+    let mut deck = Deck::with_decks(0);
+    assert_eq!(deck.num_decks(), 1);
+    assert_eq!(deck.total(), 52);
+    assert!(deck.deal().is_some());
+}
+
+/// A shuffled copy holds the same cards, in a different order
+#[rstest]
+fn shuffled_copy(mut deck_fixture: Deck) {
+    for _ in 0..5 {
+        deck_fixture.deal();
+    }
+    let copy = deck_fixture.shuffled_copy_of_remaining(&mut thread_rng());
+    assert_eq!(copy.rank_counts(), deck_fixture.rank_counts());
+    assert_eq!(copy.remaining(), 47);
+    assert_ne!(copy.to_string(), deck_fixture.to_string());
+}
+
+/// The composition report matches the cards actually left in the deck
+#[rstest]
+fn check_composition(mut deck_fixture: Deck) {
+    assert_eq!(deck_fixture.high_low_ratio(), 1.0);
+    while deck_fixture.remaining() > 40 {
+        deck_fixture.deal();
+    }
+    let report = deck_fixture.composition();
+    for rank in Rank::iter() {
+        let count = deck_fixture
+            .cards
+            .iter()
+            .filter(|c| c.rank == *rank)
+            .count();
+        let line = format!("{:>5}: {}", rank.to_string(), count);
+        assert!(report.contains(&line), "missing `{}`", line);
+    }
+    assert!(report.ends_with(&format!(
+        "High/low ratio: {:.2}",
+        deck_fixture.high_low_ratio()
+    )));
+}
+
+/// Decks built for the same round share their opening deal, but not the rest of the shoe
+#[rstest]
+fn fixed_opening_deal() {
+    let mut first = Deck::with_fixed_deal(1, 3, &mut StdRng::seed_from_u64(1));
+    let mut second = Deck::with_fixed_deal(1, 3, &mut StdRng::seed_from_u64(2));
+    for _ in 0..OPENING_DEAL_CARDS {
+        assert_eq!(first.deal(), second.deal());
+    }
+    assert_eq!(first.rank_counts(), second.rank_counts());
+    assert_ne!(first.to_string(), second.to_string());
+}
+
+/// The cut card is reached once enough of the shoe is dealt
+#[rstest]
+fn cut_card_reached(mut deck_fixture: Deck) {
+    while deck_fixture.dealt_cards().len() < 38 {
+        deck_fixture.deal();
+        assert!(!deck_fixture.needs_reshuffle(0.75));
+    }
+    deck_fixture.deal();
+    assert!(deck_fixture.needs_reshuffle(0.75));
+}
+
+/// Seeded decks and shuffles are reproducible
+#[rstest]
+fn seeded_decks() {
+    assert_eq!(
+        Deck::new_seeded(42).to_string(),
+        Deck::new_seeded(42).to_string()
+    );
+    assert_ne!(
+        Deck::new_seeded(42).to_string(),
+        Deck::new_seeded(43).to_string()
+    );
+
+    let mut first = Deck::unshuffled(1);
+    let mut second = Deck::unshuffled(1);
+    first.shuffle_seeded(7);
+    second.shuffle_seeded(7);
+    assert_eq!(first.to_string(), second.to_string());
+}
+
+/// Scenario cards are dealt in the order they are listed
+#[rstest]
+fn deck_from_scenario() {
+    let mut deck = Deck::from_scenario("AS kh\n10D  2c\n").unwrap();
+    assert_eq!(deck.total(), 4);
+    let dealt: Vec<String> = (0..4).map(|_| deck.deal().unwrap().to_string()).collect();
+    assert_eq!(
+        dealt,
+        vec![
+            "Ace of Spades",
+            "King of Hearts",
+            "10 of Diamonds",
+            "2 of Clubs"
+        ]
+    );
+}
+
+/// Scenario errors point at the offending card
+#[rstest]
+#[case("AS KH\n7D XS", "line 2, card 4 (`XS`): `X` is not a card rank")]
+#[case("AS KZ", "line 1, card 2 (`KZ`): `Z` is not a suit (H, D, S or C)")]
+#[case(" \n", "the scenario has no cards")]
+fn invalid_scenario(#[case] scenario: &str, #[case] expected: &str) {
+    assert_eq!(Deck::from_scenario(scenario).err().unwrap(), expected);
+}
+
+/// Peeking shows the next card dealt, without dealing it
+#[rstest]
+fn peek_next_card() {
+    let mut deck = Deck::new_seeded(7);
+    let next = *deck.peek().unwrap();
+    assert_eq!(deck.remaining(), 52);
+    assert_eq!(deck.deal(), Some(next));
+    assert!(Deck::from_cards(vec![]).peek().is_none());
+}
@@ -2,13 +2,23 @@
 //! File:           hand.rs
 //! Description:    Describes a hand of cards (either a dealer or player)
 //!
+use rand::{thread_rng, Rng};
 use rstest::rstest;
-use std::io::{self, Write};
-use std::{fmt, process, usize};
+use std::cmp::Ordering;
+use std::io::{self, BufRead, Write};
+use std::{fmt, process};
 
-use crate::data::probability_table::{get_action, Action};
-use crate::types::card::{Card, Rank, Suit, MAX_BLACKJACK};
-use crate::types::deck::Deck;
+use crate::data::mistakes::Decision;
+use crate::data::probability_table::{
+    get_action, get_counting_action, get_pair_action, rationale, should_surrender_under, Action,
+    INSURANCE_TRUE_COUNT,
+};
+#[cfg(test)]
+use crate::types::card::Suit;
+use crate::types::card::{Card, CardStyle, Rank, MAX_BLACKJACK};
+use crate::types::deck::{Deck, DeckEmpty};
+use crate::types::messages::{MessageKey, Messages};
+use crate::types::rules::{PushRule, Rules};
 
 /// Represents the dealer's "infinite" money pile
 pub const DEALER_INFINITE_CREDITS: isize = -1;
@@ -32,6 +42,10 @@ pub const UP_CARD_IDX: usize = 1;
 pub const DD_MIN: usize = 9;
 /// Maximum value allowed for doubling down (virtual BlackJack rules)
 pub const DD_MAX: usize = 11;
+/// Lowest soft total that may be doubled down (A+2)
+pub const SOFT_DD_MIN: usize = 13;
+/// Highest soft total that may be doubled down (A+7)
+pub const SOFT_DD_MAX: usize = 18;
 
 /// Describes the player role/strategy
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -39,14 +53,22 @@ pub enum Strategy {
     Dealer,
     Human,
     ProbabilityTable,
+    /// Hits or stands at random, modeling an unskilled player
+    Random,
+    /// Plays the probability table, deviating from it (and sizing bets) by the Hi-Lo true count
+    CardCounter,
 }
 
 /// Describes the final result of a round (from the player's perspective).
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum Outcome {
     Win,
     Loss,
     Push, // Tie
+    /// A winning natural, which pays a bonus
+    BlackJack,
+    /// The player gave up half the bet
+    Surrender,
 }
 
 /// Describes the value of a hand (handles Ace value options)
@@ -54,6 +76,50 @@ pub struct HandValue {
     lo_sum: usize,
     hi_sum: usize,
 }
+impl HandValue {
+    /// Total with every Ace counted as 1
+    pub fn low(&self) -> usize {
+        self.lo_sum
+    }
+
+    /// Total with the first Ace counted as 11, if it fits without busting when it's dealt. This may
+    /// bust later, if more cards are drawn.
+    pub fn high(&self) -> usize {
+        self.hi_sum
+    }
+
+    /// Returns true if an Ace is counted as 11 without busting, so the hand can't bust on one hit.
+    pub fn is_soft(&self) -> bool {
+        self.hi_sum != self.lo_sum && self.hi_sum <= MAX_BLACKJACK
+    }
+}
+/// Computes the low (every Ace as 1) and high (the first Ace as 11, if it fits when it's dealt) totals
+/// of `ranks`, dealt in order, without building a `Hand`. Doesn't allocate, for hot simulation loops.
+pub fn evaluate_totals(ranks: &[Rank]) -> (usize, usize) {
+    sum_ranks(ranks.iter().copied())
+}
+
+/// Low and high totals of ranks dealt in order. See `evaluate_totals()`.
+fn sum_ranks<I: Iterator<Item = Rank>>(ranks: I) -> (usize, usize) {
+    let mut lo_sum = 0;
+    let mut hi_sum = 0;
+    for rank in ranks {
+        if rank.is_ace() {
+            lo_sum += 1;
+            if hi_sum + 11 > MAX_BLACKJACK {
+                hi_sum += 1;
+            } else {
+                hi_sum += 11;
+            }
+            continue;
+        }
+
+        lo_sum += rank.value();
+        hi_sum += rank.value();
+    }
+    (lo_sum, hi_sum)
+}
+
 impl fmt::Display for HandValue {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         // Don't show the split score if it is redundant or the upper bound is a bust.
@@ -74,82 +140,124 @@ pub struct Hand {
     credits: isize,
     /// Flag used by the dealer to render the face-down card.
     show_dealer_hand: bool,
+    /// Text displayed to human players.
+    messages: Messages,
+    /// Teaching mode: explains each strategy decision (or hint, for humans).
+    explain: bool,
+    /// Card counting practice: lets human players look at what's left in the deck.
+    practice: bool,
+    /// Automatically stand human players on 21. When disabled, the player decides.
+    auto_stand: bool,
+    /// How cards are rendered.
+    card_style: CardStyle,
+    /// Rules of the table this hand is played at.
+    rules: Rules,
+    /// Set when this hand was created by splitting a pair.
+    is_split: bool,
+    /// Number of times this player may still split this round.
+    splits_remaining: usize,
+    /// A hand split off of this one, waiting to be played.
+    pending_split: Option<Box<Hand>>,
+    /// Set when the player surrendered this hand.
+    surrendered: bool,
+    /// Hi-Lo true count of the shoe, as seen by a card counter.
+    true_count: f64,
+    /// Log the decisions automated players make, to audit them later.
+    audit: bool,
+    /// Decisions logged since they were last taken.
+    decisions: Vec<Decision>,
 }
 impl Hand {
     /// Constructs a hand with the first two dealt cards.
     pub fn new(name: &str, strategy: Strategy, credits: isize) -> Self {
-        let hand = Hand {
+        Hand {
             name: String::from(name),
             cards: Vec::with_capacity(MAX_HAND_CARD_COUNT),
-            strategy: strategy,
-            credits: credits,
+            strategy,
+            credits,
             show_dealer_hand: false,
-        };
-        hand
+            messages: Messages::default(),
+            explain: false,
+            practice: false,
+            auto_stand: true,
+            card_style: CardStyle::Plain,
+            rules: Rules::default(),
+            is_split: false,
+            splits_remaining: 0,
+            pending_split: None,
+            surrendered: false,
+            true_count: 0.0,
+            audit: false,
+            decisions: Vec::new(),
+        }
     }
 
     /// Constructs a Hand from a list of cards. Used in unit testing.
     pub fn from_vector(name: &str, strategy: Strategy, vector: Vec<Card>) -> Self {
-        let hand = Hand {
-            name: String::from(name),
-            cards: vector,
-            strategy: strategy,
-            credits: HUMAN_DEFAULT_CREDITS,
-            show_dealer_hand: false,
-        };
+        let mut hand = Hand::new(name, strategy, HUMAN_DEFAULT_CREDITS);
+        hand.cards = vector;
         hand
     }
 
     /// Determines the outcome of a game based on the player's hand and the dealer's hand.
+    /// The dealer's hand holds the table rules used to settle ties.
     pub fn determine_outcome(player: &Hand, dealer: &Hand) -> Outcome {
-        let player_val = player.final_value();
-        let dealer_val = dealer.final_value();
-
-        // If the player busts, the dealer automatically wins.
-        if player_val > MAX_BLACKJACK {
-            return Outcome::Loss;
-        }
-        // If the dealer busts and you don't (checked above), you win
-        if dealer_val > MAX_BLACKJACK {
-            return Outcome::Win;
+        // Late surrender doesn't save half the bet from a dealer natural.
+        if player.surrendered {
+            if dealer.is_blackjack() && !player.rules.early_surrender {
+                return Outcome::Loss;
+            }
+            return Outcome::Surrender;
         }
-        // If there's a tie, it's a "push"
-        if player_val == dealer_val {
-            return Outcome::Push;
+        // A natural beats any other hand, including a multi-card 21. Two naturals are a tie.
+        match (player.is_blackjack(), dealer.is_blackjack()) {
+            (true, false) => return Outcome::BlackJack,
+            (false, true) => return Outcome::Loss,
+            _ => (),
         }
 
-        // The closest to BlackJack has the lowest diff. The diff must be positive at this point
-        // as we have already checked for bust scenarios.
-        let player_diff = MAX_BLACKJACK - player_val;
-        let dealer_diff = MAX_BLACKJACK - dealer_val;
-        if player_diff > dealer_diff {
+        // A player bust loses, even if the dealer busts too
+        if player.best_value().is_none() {
             return Outcome::Loss;
         }
-        Outcome::Win
+        match player.cmp_value(dealer) {
+            Ordering::Greater => Outcome::Win,
+            Ordering::Less => Outcome::Loss,
+            // If there's a tie, it's usually a "push"
+            Ordering::Equal => match dealer.rules.push_rule {
+                PushRule::Standard => Outcome::Push,
+                PushRule::DealerWinsTies => Outcome::Loss,
+                PushRule::PlayerWinsTies => Outcome::Win,
+            },
+        }
     }
 
     /// Returns the value of the hand as a tuple of options.
     /// The first value is the "low" sum, all Aces as 1.
     /// The second value is the "high sum", with 1 Ace as 11.
     pub fn value(&self) -> HandValue {
-        let mut lo_sum = 0;
-        let mut hi_sum = 0;
-        for card in self.cards.iter() {
-            if matches!(card.rank, Rank::Ace) {
-                lo_sum += 1;
-                if hi_sum + 11 > MAX_BLACKJACK {
-                    hi_sum += 1;
-                } else {
-                    hi_sum += 11;
-                }
-                continue;
-            }
+        let (lo_sum, hi_sum) = sum_ranks(self.cards.iter().map(|card| card.rank));
+        HandValue { lo_sum, hi_sum }
+    }
 
-            lo_sum += card.rank.value();
-            hi_sum += card.rank.value();
-        }
+    /// Returns the name of the player holding the hand
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
 
-        HandValue { lo_sum, hi_sum }
+    /// Returns the cards in the hand, in the order they were dealt
+    pub fn get_cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    /// Iterates over the cards in the hand, in the order they were dealt
+    pub fn iter_cards(&self) -> impl Iterator<Item = &Card> {
+        self.cards.iter()
+    }
+
+    /// Returns true if the hand has an Ace counted as 11 without busting, so it can't bust on one hit.
+    pub fn is_soft(&self) -> bool {
+        self.value().is_soft()
     }
 
     /// Returns the "final" value of the hand when the round is complete.
@@ -161,6 +269,70 @@ impl Hand {
         val.lo_sum
     }
 
+    /// Best value of the hand that doesn't bust (counting an Ace as 11 when possible), or `None` if
+    /// the hand busted.
+    pub fn best_value(&self) -> Option<usize> {
+        Some(self.final_value()).filter(|val| *val <= MAX_BLACKJACK)
+    }
+
+    /// Compares the best values of two hands that don't bust. A bust is worse than any other hand, and
+    /// ties with another bust. Naturals are not treated specially.
+    pub fn cmp_value(&self, other: &Hand) -> Ordering {
+        // `None` (a bust) orders before any value
+        self.best_value().cmp(&other.best_value())
+    }
+
+    /// Sets the rules of the table this hand is played at.
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.rules = rules;
+    }
+
+    /// Returns the rules of the table this hand is played at.
+    pub fn get_rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Replaces the message table used to render game text.
+    pub fn set_messages(&mut self, messages: Messages) {
+        self.messages = messages;
+    }
+
+    /// Returns true if the hand is a "natural": exactly two cards totaling 21, not from a split.
+    pub fn is_blackjack(&self) -> bool {
+        !self.is_split && self.cards.len() == 2 && self.final_value() == MAX_BLACKJACK
+    }
+
+    /// Returns true if the opening deal is a natural. Meant to be checked right after the deal, so the
+    /// round can be resolved before anyone acts. For the dealer, this peeks at the hole card.
+    pub fn is_natural(&self) -> bool {
+        self.is_blackjack()
+    }
+
+    /// Enables or disables card counting practice, which lets human players check the deck.
+    pub fn set_practice(&mut self, practice: bool) {
+        self.practice = practice;
+    }
+
+    /// Enables or disables teaching mode, which explains basic strategy decisions.
+    pub fn set_explain(&mut self, explain: bool) {
+        self.explain = explain;
+    }
+
+    /// Enables or disables automatically standing a human player on 21.
+    pub fn set_auto_stand(&mut self, auto_stand: bool) {
+        self.auto_stand = auto_stand;
+    }
+
+    /// Sets how the hand's cards are rendered.
+    pub fn set_card_style(&mut self, card_style: CardStyle) {
+        self.card_style = card_style;
+    }
+
+    /// Inspect how the hand's cards are rendered.
+    pub fn get_card_style(&self) -> CardStyle {
+        self.card_style
+    }
+
     /// Inspect the number of credits a player has.
     pub fn get_credits(&self) -> isize {
         self.credits
@@ -176,13 +348,79 @@ impl Hand {
         self.credits -= to_sub;
     }
 
-    /// Deals a card to the hand.
-    pub fn hit(&mut self, deck: &mut Deck) {
-        let card = deck.deal();
-        match card {
-            None => panic!("Deck ran out of cards!"),
-            Some(c) => self.cards.push(c),
-        };
+    /// Deals a card to the hand. Fails, leaving the hand alone, if the deck has run out of cards.
+    pub fn hit(&mut self, deck: &mut Deck) -> Result<(), DeckEmpty> {
+        let card = deck.deal().ok_or(DeckEmpty)?;
+        self.cards.push(card);
+        Ok(())
+    }
+
+    /// Deals a card to the hand. If the deck has run out of cards, it is replaced by a freshly shuffled
+    /// deck of the same size first, so the hand can be finished.
+    pub fn hit_or_reshuffle(&mut self, deck: &mut Deck) {
+        if self.hit(deck).is_err() {
+            *deck = Deck::with_decks(deck.num_decks());
+            self.hit(deck).expect("A fresh deck has cards");
+        }
+    }
+
+    /// Adds a specific card to the hand, without drawing from a deck. Used to build scenarios step by
+    /// step. Returns false (and leaves the hand alone) if the hand already holds the maximum number of
+    /// cards one could have before going bust.
+    pub fn add_card(&mut self, card: Card) -> bool {
+        if self.cards.len() >= MAX_HAND_CARD_COUNT {
+            return false;
+        }
+        self.cards.push(card);
+        true
+    }
+
+    /// Sets how many more times the player may split this round.
+    pub fn set_splits_remaining(&mut self, splits_remaining: usize) {
+        self.splits_remaining = splits_remaining;
+    }
+
+    /// Returns the rank of the pair if the hand is exactly two cards of the same rank. With
+    /// `ten_values_pair`, any two ten-valued cards (i.e. a King and a Ten) are a pair of Tens.
+    pub fn as_pair(&self, ten_values_pair: bool) -> Option<Rank> {
+        match self.cards[..] {
+            [first, second] if first.rank == second.rank => Some(first.rank),
+            [first, second]
+                if ten_values_pair && first.rank.is_ten_value() && second.rank.is_ten_value() =>
+            {
+                Some(Rank::Ten)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns true if splitting is currently allowed: the opening two cards share the same value, and
+    /// the player can afford a second bet.
+    pub fn can_split(&self, bet: isize) -> bool {
+        self.splits_remaining > 0 && self.credits >= bet && self.as_pair(true).is_some()
+    }
+
+    /// Splits a pair into two hands, placing a second `bet` on the new hand. Each hand is then dealt
+    /// one more card. The new hand waits to be played and can be retrieved with `take_split()`.
+    pub fn split(&mut self, deck: &mut Deck, bet: isize) {
+        self.sub_credits(bet);
+        let mut split_hand = self.clone();
+        // The player's credits stay with the hand being played.
+        split_hand.credits = 0;
+        split_hand.cards.clear();
+        split_hand.cards.extend(self.cards.pop());
+        split_hand.is_split = true;
+        split_hand.decisions.clear();
+        self.is_split = true;
+
+        self.hit_or_reshuffle(deck);
+        split_hand.hit_or_reshuffle(deck);
+        self.pending_split = Some(Box::new(split_hand));
+    }
+
+    /// Takes the hand created by the most recent split, if there is one.
+    pub fn take_split(&mut self) -> Option<Hand> {
+        self.pending_split.take().map(|h| *h)
     }
 
     /// Returns true if doubling down is currently allowed
@@ -191,19 +429,108 @@ impl Hand {
         if self.credits < bet {
             return false;
         }
+        // Split hands may only be doubled when the table allows it
+        if self.is_split && !self.rules.das {
+            return false;
+        }
+        // Soft hands are doubled on the totals basic strategy doubles them on, as dealt
+        if self.is_soft()
+            && self.cards.len() == 2
+            && (SOFT_DD_MIN..=SOFT_DD_MAX).contains(&self.final_value())
+        {
+            return true;
+        }
         // The exact rules aren't publicized and probably aren't consistent from BlackJack machine to machine or casino
         // to casino.
         let val = self.value().lo_sum;
-        val >= DD_MIN && val <= DD_MAX
+        (DD_MIN..=DD_MAX).contains(&val)
     }
 
     /// A double down is a single hit that doubles the bet. Returns the new bet.
     pub fn double_down(&mut self, deck: &mut Deck, bet: isize) -> isize {
         self.sub_credits(bet);
-        self.hit(deck);
+        self.hit_or_reshuffle(deck);
         2 * bet
     }
 
+    /// Sets the true count the card counter plays the next decisions by.
+    pub fn set_true_count(&mut self, true_count: f64) {
+        self.true_count = true_count;
+    }
+
+    /// Enables or disables logging the decisions of automated players.
+    pub fn set_audit(&mut self, audit: bool) {
+        self.audit = audit;
+    }
+
+    /// Takes the decisions logged so far.
+    pub fn take_decisions(&mut self) -> Vec<Decision> {
+        std::mem::take(&mut self.decisions)
+    }
+
+    /// Logs a decision, if auditing is enabled.
+    fn log_decision(&mut self, up_card: Rank, action: Action) {
+        if self.audit {
+            self.decisions.push(Decision {
+                cards: self.cards.clone(),
+                up_card,
+                action,
+                split: self.is_split,
+            });
+        }
+    }
+
+    /// Returns true if surrendering is currently allowed: the table offers surrender and the player
+    /// has only their opening two cards.
+    pub fn can_surrender(&self) -> bool {
+        (self.rules.late_surrender || self.rules.early_surrender)
+            && self.cards.len() == 2
+            && !self.is_split
+    }
+
+    /// Gives up the hand. The player gets half the bet back when the hand is settled.
+    pub fn surrender(&mut self) {
+        self.surrendered = true;
+    }
+
+    /// Returns true if the player gave up this hand.
+    pub fn is_surrendered(&self) -> bool {
+        self.surrendered
+    }
+
+    /// Offers early surrender on the opening two cards, before the dealer peeks for BlackJack, when the
+    /// table allows it. Human players answer from `input`. Returns true if the player surrendered.
+    pub fn offer_early_surrender<R: BufRead>(&mut self, up_card: Rank, input: &mut R) -> bool {
+        if !self.rules.early_surrender || !self.can_surrender() || self.is_natural() {
+            return false;
+        }
+        let take = match self.strategy {
+            Strategy::Dealer | Strategy::Random => false,
+            // Basic strategy gives up the same hands it would surrender late, and splits pairs first.
+            Strategy::ProbabilityTable | Strategy::CardCounter => {
+                let splits = self.cards[0].rank.value() == self.cards[1].rank.value()
+                    && get_pair_action(self.cards[0].rank, up_card, self.rules.das).is_some();
+                !splits
+                    && !self.is_soft()
+                    && should_surrender_under(self.final_value(), up_card, &self.rules)
+            }
+            Strategy::Human => {
+                print!("Surrender before the dealer checks for BlackJack? Su(R)render | (N)o > ");
+                let _ = io::stdout().flush();
+                let mut answer = String::new();
+                input
+                    .read_line(&mut answer)
+                    .expect("Failed to read user input");
+                matches!(answer.trim().to_lowercase().as_str(), "r" | "surrender")
+            }
+        };
+        if take {
+            self.log_decision(up_card, Action::Surrender);
+            self.surrender();
+        }
+        take
+    }
+
     /// Returns the rank of the up card. Can only be used on the dealer.
     pub fn get_up_card_rank(&self) -> Rank {
         if self.strategy != Strategy::Dealer {
@@ -212,94 +539,288 @@ impl Hand {
         self.cards[UP_CARD_IDX].rank
     }
 
+    /// Peeks at the down card to check for a natural, without revealing it. Can only be used on the
+    /// dealer.
+    pub fn has_blackjack_with_hole(&self) -> bool {
+        if self.strategy != Strategy::Dealer {
+            panic!("There is no `hole card` for non-dealer players.")
+        }
+        self.is_blackjack()
+    }
+
+    /// Under US rules, the dealer peeks at the down card after the deal when showing an Ace or a
+    /// ten-value card. Returns true if the peek finds a natural. Can only be used on the dealer.
+    pub fn peek_for_blackjack(&self) -> bool {
+        let up_card = self.get_up_card_rank();
+        (up_card.is_ace() || up_card.is_ten_value()) && self.has_blackjack_with_hole()
+    }
+
+    /// Offers an insurance side bet of half the main `bet` when the dealer shows an Ace. Only offered
+    /// on the opening two cards, and not on a bet too small to halve. Human players answer from
+    /// `input`. Returns the side bet taken (which is removed from the player's credits), or 0 if
+    /// insurance was declined or not offered.
+    pub fn offer_insurance<R: BufRead>(
+        &mut self,
+        bet: isize,
+        up_card: Rank,
+        input: &mut R,
+    ) -> isize {
+        let side_bet = bet / 2;
+        if up_card != Rank::Ace
+            || self.cards.len() != 2
+            || self.is_split
+            || side_bet <= 0
+            || self.credits < side_bet
+        {
+            return 0;
+        }
+        let take = match self.strategy {
+            // Basic strategy never takes insurance, it's a losing bet without counting cards.
+            Strategy::Dealer | Strategy::ProbabilityTable => false,
+            Strategy::Random => thread_rng().gen_bool(0.5),
+            // Insurance pays when the shoe is rich in tens
+            Strategy::CardCounter => self.true_count >= INSURANCE_TRUE_COUNT,
+            Strategy::Human => {
+                print!(
+                    "Dealer shows an Ace. Insurance for ${}? (I)nsurance | (N)o > ",
+                    side_bet
+                );
+                let _ = io::stdout().flush();
+                let mut answer = String::new();
+                input
+                    .read_line(&mut answer)
+                    .expect("Failed to read user input");
+                matches!(answer.trim().to_lowercase().as_str(), "i" | "insurance")
+            }
+        };
+        if !take {
+            return 0;
+        }
+        self.sub_credits(side_bet);
+        side_bet
+    }
+
+    /// Offers even money (a guaranteed 1:1 payout) on a natural when the dealer shows an Ace, instead
+    /// of risking a push against a dealer natural. Human players answer from `input`. Returns `None`
+    /// if even money isn't offered, otherwise whether it was taken.
+    pub fn offer_even_money<R: BufRead>(&mut self, up_card: Rank, input: &mut R) -> Option<bool> {
+        if up_card != Rank::Ace || !self.is_natural() {
+            return None;
+        }
+        let take = match self.strategy {
+            // Even money is worth less than the 3:2 payout on average, so simulated players decline.
+            Strategy::Dealer | Strategy::ProbabilityTable | Strategy::CardCounter => false,
+            Strategy::Random => thread_rng().gen_bool(0.5),
+            Strategy::Human => {
+                print!("BlackJack! Dealer shows an Ace. Take even money? (E)ven money | (N)o > ");
+                let _ = io::stdout().flush();
+                let mut answer = String::new();
+                input
+                    .read_line(&mut answer)
+                    .expect("Failed to read user input");
+                matches!(
+                    answer.trim().to_lowercase().as_str(),
+                    "e" | "even" | "even money"
+                )
+            }
+        };
+        Some(take)
+    }
+
     /// Shows the dealer's full hand when rendered.
     pub fn show_hand(&mut self) {
         self.show_dealer_hand = true;
     }
 
+    /// Flips the dealer's down card face-up, returning it. Can only be used on the dealer.
+    pub fn reveal_hole_card(&mut self) -> Card {
+        if self.strategy != Strategy::Dealer {
+            panic!("There is no `hole card` for non-dealer players.")
+        }
+        self.show_hand();
+        self.cards[DOWN_CARD_IDX]
+    }
+
     /// Clears the hand the player currently has. Does not reset credits or other state.
     pub fn clear_hand(&mut self) {
         self.cards.clear();
+        self.is_split = false;
+        self.pending_split = None;
+        self.surrendered = false;
         // Reset the dealer's rendering flag
         self.show_dealer_hand = false;
     }
 
+    /// Teaching mode's reasoning for taking `action` against the dealer's `up_card`. Card counters also
+    /// show the true count the decision was based on.
+    fn explanation(&self, up_card: Rank, action: &Action) -> String {
+        let reason = rationale(self.final_value(), up_card, action);
+        match self.strategy {
+            Strategy::CardCounter => format!("{} (true count {:+.1})", reason, self.true_count),
+            _ => reason,
+        }
+    }
+
     /// Dealer simulation. Returns true if the dealer stops.
     fn play_dealer(&mut self, deck: &mut Deck) -> bool {
-        // Optionally print game moves. Add some delay for human readability.
         let hand_val = self.value();
+        let threshold = self.rules.dealer_stands;
         // Dealer met the threshold, bust, or got BlackJack
-        if hand_val.lo_sum >= DEALER_HAND_THRESHOLD {
+        if hand_val.lo_sum >= threshold {
             return true;
         }
-        // Dealer met the threshold by counting the 1st Ace as 11 without busting.
-        if hand_val.hi_sum < MAX_BLACKJACK && hand_val.hi_sum >= DEALER_HAND_THRESHOLD {
+        // Dealer met the threshold by counting the 1st Ace as 11 without busting. Some tables make
+        // the dealer hit a soft 17.
+        let soft_17 = hand_val.hi_sum == DEALER_HAND_THRESHOLD;
+        if hand_val.hi_sum <= MAX_BLACKJACK
+            && hand_val.hi_sum >= threshold
+            && !(soft_17 && self.rules.dealer_hits_soft_17)
+        {
             return true;
         }
-        self.hit(deck);
+        self.hit_or_reshuffle(deck);
         false
     }
 
-    /// Perfect use of the probability table simulation. Returns true if the player quit.
+    /// Perfect use of the probability table simulation (adjusted by the count, for card counters).
+    /// Returns true if the player quit.
     fn play_probability_table(
         &mut self,
         deck: &mut Deck,
         bet: isize,
         up_card: Rank,
     ) -> (bool, isize) {
-        match get_action(self.final_value(), up_card) {
-            Action::Hit => self.hit(deck),
+        let soft = self.is_soft();
+        // Pairs are checked first, then the totals.
+        let pair_action = get_pair_action(self.cards[0].rank, up_card, self.rules.das);
+        let action = match pair_action {
+            // Nothing beats a 21, hard or soft
+            _ if self.final_value() == MAX_BLACKJACK => Action::Stand,
+            Some(action) if self.can_split(bet) => action,
+            _ if self.can_surrender()
+                && !soft
+                && should_surrender_under(self.final_value(), up_card, &self.rules) =>
+            {
+                Action::Surrender
+            }
+            _ if self.strategy == Strategy::CardCounter => {
+                get_counting_action(self.final_value(), soft, up_card, self.true_count)
+            }
+            _ => get_action(self.final_value(), soft, up_card),
+        };
+        if self.explain {
+            println!("{}", self.explanation(up_card, &action));
+        }
+        self.log_decision(up_card, action);
+        match action {
+            Action::Hit => self.hit_or_reshuffle(deck),
             Action::DoubleDown => {
                 // Can't double down if there are insufficient funds
                 if self.can_double_down(bet) {
                     return (true, self.double_down(deck, bet));
                 }
-                self.hit(deck)
+                // A soft 18 is strong enough to stand on, when it can't be doubled
+                if soft && self.final_value() >= 18 {
+                    return (true, bet);
+                }
+                self.hit_or_reshuffle(deck)
+            }
+            Action::Split => self.split(deck, bet),
+            Action::Surrender => {
+                self.surrender();
+                return (true, bet);
             }
             Action::Stand => return (true, bet),
         }
         (false, bet)
     }
 
+    /// Random play (coin flip between hitting and standing). Returns true if the player stops.
+    fn play_random(&mut self, deck: &mut Deck, up_card: Rank) -> bool {
+        if self.value().lo_sum >= MAX_BLACKJACK {
+            return true;
+        }
+        if thread_rng().gen_bool(0.5) {
+            self.log_decision(up_card, Action::Stand);
+            return true;
+        }
+        self.log_decision(up_card, Action::Hit);
+        self.hit_or_reshuffle(deck);
+        false
+    }
+
     /// UI for human playable games. Returns true if the player quit.
-    fn play_human(&mut self, deck: &mut Deck, bet: isize) -> (bool, isize) {
+    fn play_human<R: BufRead>(
+        &mut self,
+        deck: &mut Deck,
+        bet: isize,
+        up_card: Rank,
+        input: &mut R,
+    ) -> (bool, isize) {
         // End early if user ran out of money
         if self.get_credits() <= 0 {
-            println!("You're out of money! Good say, sir!");
+            println!("{}", self.messages.get(MessageKey::OutOfMoney));
             return (true, bet);
         }
-        // Auto-terminates on BlackJack and bust
+        // Auto-terminates on BlackJack (unless the player wants to decide) and bust
         {
             let cur_val = self.value();
-            if cur_val.lo_sum == MAX_BLACKJACK || cur_val.hi_sum == MAX_BLACKJACK {
-                println!("BlackJack!");
+            if self.auto_stand
+                && (cur_val.lo_sum == MAX_BLACKJACK || cur_val.hi_sum == MAX_BLACKJACK)
+            {
+                println!("{}", self.messages.get(MessageKey::BlackJack));
                 return (true, bet);
             }
             if cur_val.lo_sum > MAX_BLACKJACK {
-                println!("Bust!");
+                println!("{}", self.messages.get(MessageKey::Bust));
                 return (true, bet);
             }
         }
 
+        // Teaching mode offers the basic strategy move as a hint
+        if self.explain {
+            let hint = match get_pair_action(self.cards[0].rank, up_card, self.rules.das) {
+                Some(action) if self.can_split(bet) => action,
+                _ => get_action(self.final_value(), self.is_soft(), up_card),
+            };
+            println!("Hint: {}", rationale(self.final_value(), up_card, &hint));
+        }
+
         let mut action = String::new();
 
-        // Conditionally enable double down based on total and if there's enough credits.
+        // Conditionally enable double down and splitting based on the cards and if there's enough credits.
+        let mut options = String::from("(H)it");
         if self.can_double_down(bet) {
-            print!("Bet: ${} | (H)it | (D)ouble Down | (S)tay | (Q)uit > ", bet);
-        } else {
-            print!("Bet: ${} | (H)it | (S)tay | (Q)uit > ", bet);
+            options.push_str(" | (D)ouble Down");
         }
+        if self.can_split(bet) {
+            options.push_str(" | S(P)lit");
+        }
+        if self.can_surrender() {
+            options.push_str(" | Su(R)render");
+        }
+        if self.practice {
+            options.push_str(" | Dec(K)");
+        }
+        print!("Bet: ${} | {} | (S)tay | (Q)uit > ", bet, options);
         let _ = io::stdout().flush();
-        io::stdin()
+        input
             .read_line(&mut action)
             .expect("Failed to read user input");
 
         match action.trim().to_lowercase().as_str() {
-            "h" | "hit" => self.hit(deck),
+            "h" | "hit" => self.hit_or_reshuffle(deck),
             "d" | "double" | "double down" | "neil breen" if self.can_double_down(bet) => {
-                println!("Double down! (Neil would be proud)");
+                println!("{}", self.messages.get(MessageKey::DoubleDown));
                 return (true, self.double_down(deck, bet));
             }
+            "p" | "split" if self.can_split(bet) => self.split(deck, bet),
+            "r" | "surrender" if self.can_surrender() => {
+                self.surrender();
+                return (true, bet);
+            }
+            // Looking at the deck doesn't use up a move
+            "k" | "deck" if self.practice => println!("{}", deck.composition()),
             "s" | "stay" | "stand" => return (true, bet),
             "q" | "quit" => process::exit(0),
             _ => (),
@@ -309,10 +830,28 @@ impl Hand {
 
     /// Executes 1 play action based on strategy. Returns true if the player stops.
     pub fn play_once(&mut self, deck: &mut Deck, bet: isize, up_card: Rank) -> (bool, isize) {
+        // Only humans read stdin. Locking it otherwise would deadlock a game already reading it.
+        match self.strategy {
+            Strategy::Human => self.play_once_from(deck, bet, up_card, &mut io::stdin().lock()),
+            _ => self.play_once_from(deck, bet, up_card, &mut io::empty()),
+        }
+    }
+
+    /// Same as `play_once`, but human players read their moves from `input`. Used to script games.
+    pub fn play_once_from<R: BufRead>(
+        &mut self,
+        deck: &mut Deck,
+        bet: isize,
+        up_card: Rank,
+        input: &mut R,
+    ) -> (bool, isize) {
         match self.strategy {
             Strategy::Dealer => (self.play_dealer(deck), NO_BET_VALUE),
-            Strategy::ProbabilityTable => self.play_probability_table(deck, bet, up_card),
-            Strategy::Human => self.play_human(deck, bet),
+            Strategy::ProbabilityTable | Strategy::CardCounter => {
+                self.play_probability_table(deck, bet, up_card)
+            }
+            Strategy::Human => self.play_human(deck, bet, up_card, input),
+            Strategy::Random => (self.play_random(deck, up_card), bet),
         }
     }
 }
@@ -327,15 +866,23 @@ impl fmt::Display for Hand {
                         writeln!(f, "  <DOWN CARD>").expect("I/O Error");
                         continue;
                     }
-                    writeln!(f, "  {}", card).expect("I/O Error");
+                    writeln!(f, "  {}", card.styled(self.card_style)).expect("I/O Error");
                 }
                 Ok(())
             }
             _ => {
-                writeln!(f, "{} ({}) | ${}", self.name, self.value(), self.credits)
-                    .expect("I/O Error");
+                let soft = if self.is_soft() { " (soft)" } else { "" };
+                writeln!(
+                    f,
+                    "{} ({}){} | ${}",
+                    self.name,
+                    self.value(),
+                    soft,
+                    self.credits
+                )
+                .expect("I/O Error");
                 for card in self.cards.iter() {
-                    writeln!(f, "  {}", card).expect("I/O Error");
+                    writeln!(f, "  {}", card.styled(self.card_style)).expect("I/O Error");
                 }
                 Ok(())
             }
@@ -382,7 +929,7 @@ impl fmt::Display for Hand {
 #[case(
     vec![Card{suit: Suit::Clubs, rank: Rank::Jack}, Card{suit: Suit::Clubs, rank: Rank::Ace}],
     vec![Card{suit: Suit::Diamonds, rank: Rank::Jack}, Card{suit: Suit::Diamonds, rank: Rank::King}],
-    Outcome::Win
+    Outcome::BlackJack
 )]
 #[case(
     vec![Card{suit: Suit::Diamonds, rank: Rank::Jack}, Card{suit: Suit::Diamonds, rank: Rank::King}],
@@ -394,6 +941,17 @@ impl fmt::Display for Hand {
     vec![Card{suit: Suit::Diamonds, rank: Rank::Jack}, Card{suit: Suit::Diamonds, rank: Rank::Ace}],
     Outcome::Push
 )]
+// Naturals versus multi-card 21s
+#[case(
+    vec![Card{suit: Suit::Clubs, rank: Rank::Seven}, Card{suit: Suit::Clubs, rank: Rank::Four}, Card{suit: Suit::Clubs, rank: Rank::Queen}],
+    vec![Card{suit: Suit::Diamonds, rank: Rank::Ace}, Card{suit: Suit::Diamonds, rank: Rank::King}],
+    Outcome::Loss
+)]
+#[case(
+    vec![Card{suit: Suit::Clubs, rank: Rank::Ace}, Card{suit: Suit::Clubs, rank: Rank::Queen}],
+    vec![Card{suit: Suit::Diamonds, rank: Rank::Seven}, Card{suit: Suit::Diamonds, rank: Rank::Four}, Card{suit: Suit::Diamonds, rank: Rank::King}],
+    Outcome::BlackJack
+)]
 // Bust scenarios
 #[case(
     vec![Card{suit: Suit::Clubs, rank: Rank::Jack}, Card{suit: Suit::Clubs, rank: Rank::Three}],
@@ -419,3 +977,939 @@ fn check_outcome(
     let dealer = Hand::from_vector("dealer", Strategy::Dealer, dealer_cards);
     assert_eq!(Hand::determine_outcome(&player, &dealer), expected)
 }
+
+/// A bust orders below any hand that didn't bust, no matter the raw sum
+#[rstest]
+#[case(&[Rank::Ten, Rank::Ten, Rank::Two], &[Rank::Two, Rank::Three], Ordering::Less)]
+#[case(&[Rank::King, Rank::Queen, Rank::Jack], &[Rank::Ten, Rank::Ace, Rank::Ace], Ordering::Less)]
+#[case(&[Rank::Ten, Rank::Nine], &[Rank::Ace, Rank::Seven], Ordering::Greater)]
+#[case(&[Rank::Ace, Rank::Nine], &[Rank::Ten, Rank::Seven, Rank::Three], Ordering::Equal)]
+#[case(&[Rank::Ten, Rank::Ten, Rank::Five], &[Rank::Nine, Rank::Nine, Rank::Nine], Ordering::Equal)]
+fn check_cmp_value(#[case] first: &[Rank], #[case] second: &[Rank], #[case] expected: Ordering) {
+    let hand_of = |ranks: &[Rank]| {
+        let cards = ranks
+            .iter()
+            .map(|rank| Card {
+                suit: Suit::Clubs,
+                rank: *rank,
+            })
+            .collect();
+        Hand::from_vector("player", Strategy::ProbabilityTable, cards)
+    };
+    assert_eq!(hand_of(first).cmp_value(&hand_of(second)), expected);
+    assert_eq!(
+        hand_of(second).cmp_value(&hand_of(first)),
+        expected.reverse()
+    );
+
+    // Hands can be sorted from worst to best
+    let mut hands = [hand_of(first), hand_of(second)];
+    hands.sort_by(|a, b| a.cmp_value(b));
+    assert_ne!(hands[0].cmp_value(&hands[1]), Ordering::Greater);
+}
+
+/// Soft hands are compared by their best value that doesn't bust
+#[rstest]
+// Soft 21 in three cards ties a hard 21 in three cards
+#[case(&[Rank::Ace, Rank::Five, Rank::Five], &[Rank::Ten, Rank::Five, Rank::Six], Outcome::Push)]
+// Soft 21 needing two Aces as 1 ties a soft 21
+#[case(&[Rank::Ace, Rank::Nine, Rank::Ace], &[Rank::Ace, Rank::Ace, Rank::Eight, Rank::Ace], Outcome::Push)]
+// A natural beats a three card 21
+#[case(&[Rank::Ace, Rank::Ten], &[Rank::Seven, Rank::Seven, Rank::Seven], Outcome::BlackJack)]
+// Hard 17 made with an Ace ties a soft 17
+#[case(&[Rank::Ace, Rank::Six, Rank::Ten], &[Rank::Ace, Rank::Six], Outcome::Push)]
+// Two Aces and a 9 are 21, not a bust
+#[case(&[Rank::Ace, Rank::Ace, Rank::Nine], &[Rank::Ten, Rank::King], Outcome::Win)]
+// Soft 19 beats soft 18
+#[case(&[Rank::Ace, Rank::Eight], &[Rank::Ace, Rank::Seven], Outcome::Win)]
+// Soft 16 loses to 17
+#[case(&[Rank::Ace, Rank::Five], &[Rank::Ten, Rank::Seven], Outcome::Loss)]
+// Hard 12 with two Aces beats a dealer bust
+#[case(&[Rank::Ten, Rank::Ace, Rank::Ace], &[Rank::Ten, Rank::Five, Rank::Seven], Outcome::Win)]
+// A soft hand that busts even counting the Ace as 1 loses, even to a dealer bust
+#[case(&[Rank::Ace, Rank::Five, Rank::Ten, Rank::Six], &[Rank::Ten, Rank::Six, Rank::Ten], Outcome::Loss)]
+fn check_soft_outcome(
+    #[case] player_ranks: &[Rank],
+    #[case] dealer_ranks: &[Rank],
+    #[case] expected: Outcome,
+) {
+    let cards_of = |ranks: &[Rank]| {
+        ranks
+            .iter()
+            .map(|rank| Card {
+                suit: Suit::Hearts,
+                rank: *rank,
+            })
+            .collect()
+    };
+    let player = Hand::from_vector("player", Strategy::ProbabilityTable, cards_of(player_ranks));
+    let dealer = Hand::from_vector("dealer", Strategy::Dealer, cards_of(dealer_ranks));
+    assert_eq!(Hand::determine_outcome(&player, &dealer), expected)
+}
+
+/// A human on 21 stands automatically, unless auto-stand is disabled.
+#[rstest]
+#[case(true, true, 2)]
+#[case(false, false, 3)]
+fn human_auto_stand(
+    #[case] auto_stand: bool,
+    #[case] expected_stop: bool,
+    #[case] expected_cards: usize,
+) {
+    let mut deck = Deck::new();
+    let mut human = Hand::from_vector(
+        "human",
+        Strategy::Human,
+        vec![
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Ten,
+            },
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Ace,
+            },
+        ],
+    );
+    human.set_auto_stand(auto_stand);
+    let (stop, _) = human.play_once_from(
+        &mut deck,
+        DEFAULT_BET_VALUE,
+        Rank::Six,
+        &mut "h\n".as_bytes(),
+    );
+    assert_eq!(stop, expected_stop);
+    assert_eq!(human.cards.len(), expected_cards);
+}
+
+/// Adding cards one at a time updates the hand's value and stops at the maximum card count.
+#[rstest]
+fn add_cards_one_by_one() {
+    let mut hand = Hand::new("player", Strategy::ProbabilityTable, HUMAN_DEFAULT_CREDITS);
+    assert!(hand.add_card(Card {
+        suit: Suit::Clubs,
+        rank: Rank::Ace
+    }));
+    assert_eq!(hand.value().to_string(), "1/11");
+    assert!(hand.add_card(Card {
+        suit: Suit::Clubs,
+        rank: Rank::Six
+    }));
+    assert_eq!(hand.value().to_string(), "7/17");
+    assert!(hand.add_card(Card {
+        suit: Suit::Clubs,
+        rank: Rank::King
+    }));
+    assert_eq!(hand.final_value(), 17);
+
+    hand.clear_hand();
+    for _ in 0..MAX_HAND_CARD_COUNT {
+        assert!(hand.add_card(Card {
+            suit: Suit::Hearts,
+            rank: Rank::Two
+        }));
+    }
+    assert!(!hand.add_card(Card {
+        suit: Suit::Hearts,
+        rank: Rank::Two
+    }));
+    assert_eq!(hand.cards.len(), MAX_HAND_CARD_COUNT);
+}
+
+/// Ties are settled according to the table's push rule.
+#[rstest]
+#[case(PushRule::Standard, Outcome::Push)]
+#[case(PushRule::DealerWinsTies, Outcome::Loss)]
+#[case(PushRule::PlayerWinsTies, Outcome::Win)]
+fn check_push_rule(#[case] push_rule: PushRule, #[case] expected: Outcome) {
+    let player = Hand::from_vector(
+        "player",
+        Strategy::ProbabilityTable,
+        vec![
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Jack,
+            },
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Queen,
+            },
+        ],
+    );
+    let mut dealer = Hand::from_vector(
+        "dealer",
+        Strategy::Dealer,
+        vec![
+            Card {
+                suit: Suit::Diamonds,
+                rank: Rank::King,
+            },
+            Card {
+                suit: Suit::Diamonds,
+                rank: Rank::Ten,
+            },
+        ],
+    );
+    dealer.set_rules(Rules {
+        push_rule,
+        ..Rules::default()
+    });
+    assert_eq!(Hand::determine_outcome(&player, &dealer), expected)
+}
+
+/// Dealt a hard 16 against a dealer 6, the probability table stands instead of drawing the 5
+#[rstest]
+fn table_stands_on_scripted_deal() {
+    let card = |rank| Card {
+        suit: Suit::Diamonds,
+        rank,
+    };
+    // Dealt from the end: player, dealer, player, dealer, then the next draw
+    let mut deck = Deck::from_cards(vec![
+        card(Rank::Five),
+        card(Rank::Six),
+        card(Rank::Six),
+        card(Rank::Ten),
+        card(Rank::King),
+    ]);
+    let mut player = Hand::new("player", Strategy::ProbabilityTable, HUMAN_DEFAULT_CREDITS);
+    let mut dealer = Hand::new("dealer", Strategy::Dealer, HUMAN_DEFAULT_CREDITS);
+    for _ in 0..2 {
+        player.hit(&mut deck).unwrap();
+        dealer.hit(&mut deck).unwrap();
+    }
+    assert_eq!(player.final_value(), 16);
+    assert_eq!(dealer.get_up_card_rank(), Rank::Six);
+
+    let (stop, bet) = player.play_once(&mut deck, DEFAULT_BET_VALUE, dealer.get_up_card_rank());
+    assert!(stop);
+    assert_eq!(bet, DEFAULT_BET_VALUE);
+    assert_eq!(player.get_cards().len(), 2);
+    assert_eq!(deck.remaining(), 1);
+}
+
+/// Two cards of the same rank are a pair. Mixed ten-valued cards are only a pair when asked for.
+#[rstest]
+#[case(Rank::Eight, Rank::Eight, false, Some(Rank::Eight))]
+#[case(Rank::Eight, Rank::Eight, true, Some(Rank::Eight))]
+#[case(Rank::King, Rank::Ten, true, Some(Rank::Ten))]
+#[case(Rank::King, Rank::Ten, false, None)]
+#[case(Rank::King, Rank::King, false, Some(Rank::King))]
+#[case(Rank::Eight, Rank::Nine, true, None)]
+fn check_as_pair(
+    #[case] first: Rank,
+    #[case] second: Rank,
+    #[case] ten_values_pair: bool,
+    #[case] expected: Option<Rank>,
+) {
+    let hand = Hand::from_vector(
+        "player",
+        Strategy::Human,
+        vec![
+            Card {
+                suit: Suit::Clubs,
+                rank: first,
+            },
+            Card {
+                suit: Suit::Hearts,
+                rank: second,
+            },
+        ],
+    );
+    assert_eq!(hand.as_pair(ten_values_pair), expected);
+}
+
+/// Splitting a pair moves the second card to a new hand, bets again, and deals a card to each hand.
+#[rstest]
+fn split_pair() {
+    let mut deck = Deck::new();
+    let mut player = Hand::from_vector(
+        "player",
+        Strategy::Human,
+        vec![
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Eight,
+            },
+            Card {
+                suit: Suit::Hearts,
+                rank: Rank::Eight,
+            },
+        ],
+    );
+    assert!(!player.can_split(DEFAULT_BET_VALUE));
+    player.set_splits_remaining(1);
+    assert!(player.can_split(DEFAULT_BET_VALUE));
+
+    let (stop, bet) = player.play_once_from(
+        &mut deck,
+        DEFAULT_BET_VALUE,
+        Rank::Six,
+        &mut "p\n".as_bytes(),
+    );
+    assert!(!stop);
+    assert_eq!(bet, DEFAULT_BET_VALUE);
+    assert_eq!(
+        player.get_credits(),
+        HUMAN_DEFAULT_CREDITS - DEFAULT_BET_VALUE
+    );
+
+    let split_hand = player.take_split().unwrap();
+    assert!(player.take_split().is_none());
+    assert_eq!(player.cards.len(), 2);
+    assert_eq!(split_hand.cards.len(), 2);
+    assert!(matches!(player.cards[0].rank, Rank::Eight));
+    assert!(matches!(split_hand.cards[0].rank, Rank::Eight));
+    assert_eq!(split_hand.get_credits(), 0);
+}
+
+/// A 21 made from a split Ace is not a natural.
+#[rstest]
+fn split_aces_not_blackjack() {
+    let mut deck = Deck::new();
+    let mut player = Hand::from_vector(
+        "player",
+        Strategy::ProbabilityTable,
+        vec![
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Ace,
+            },
+            Card {
+                suit: Suit::Hearts,
+                rank: Rank::Ace,
+            },
+        ],
+    );
+    player.set_splits_remaining(1);
+    player.split(&mut deck, DEFAULT_BET_VALUE);
+    player.cards[1] = Card {
+        suit: Suit::Hearts,
+        rank: Rank::King,
+    };
+    assert_eq!(player.final_value(), MAX_BLACKJACK);
+    assert!(!player.is_blackjack());
+}
+
+/// A split hand can only be doubled when the table allows doubling after a split
+#[rstest]
+#[case(true)]
+#[case(false)]
+fn double_after_split(#[case] das: bool) {
+    let five = Card {
+        suit: Suit::Clubs,
+        rank: Rank::Five,
+    };
+    let six = Card {
+        suit: Suit::Hearts,
+        rank: Rank::Six,
+    };
+    let mut deck = Deck::from_cards(vec![six, six]);
+    let mut player = Hand::from_vector("player", Strategy::ProbabilityTable, vec![five, five]);
+    player.set_rules(Rules {
+        das,
+        ..Rules::default()
+    });
+    player.set_splits_remaining(1);
+    player.split(&mut deck, DEFAULT_BET_VALUE);
+    assert_eq!(player.final_value(), 11);
+    assert_eq!(player.can_double_down(DEFAULT_BET_VALUE), das);
+}
+
+/// Basic strategy doubles a soft 17 (A+6) against a 5, and the hand allows it
+#[rstest]
+fn probability_table_doubles_soft_hand() {
+    let card = |rank| Card {
+        suit: Suit::Spades,
+        rank,
+    };
+    let mut deck = Deck::from_cards(vec![card(Rank::Four)]);
+    let mut player = Hand::from_vector(
+        "player",
+        Strategy::ProbabilityTable,
+        vec![card(Rank::Ace), card(Rank::Six)],
+    );
+    assert!(player.can_double_down(DEFAULT_BET_VALUE));
+    assert_eq!(
+        player.play_once(&mut deck, DEFAULT_BET_VALUE, Rank::Five),
+        (true, 2 * DEFAULT_BET_VALUE)
+    );
+    assert_eq!(player.cards.len(), 3);
+}
+
+/// A soft total is only doubled on the opening two cards, not after drawing to it
+#[rstest]
+#[case(vec![Rank::Ace, Rank::Six], true)]
+#[case(vec![Rank::Ace, Rank::Two, Rank::Four], false)]
+fn soft_double_needs_two_cards(#[case] ranks: Vec<Rank>, #[case] expected: bool) {
+    let cards = ranks
+        .into_iter()
+        .map(|rank| Card {
+            suit: Suit::Spades,
+            rank,
+        })
+        .collect();
+    let player = Hand::from_vector("player", Strategy::ProbabilityTable, cards);
+    assert_eq!(player.final_value(), 17);
+    assert_eq!(player.can_double_down(DEFAULT_BET_VALUE), expected);
+}
+
+/// Pairs can't be split without a split remaining, and mismatched cards can't be split at all.
+#[rstest]
+#[case(Rank::King, Rank::Ten, 1, true)]
+#[case(Rank::Eight, Rank::Nine, 1, false)]
+#[case(Rank::Ace, Rank::Ace, 0, false)]
+fn check_can_split(
+    #[case] first: Rank,
+    #[case] second: Rank,
+    #[case] splits_remaining: usize,
+    #[case] expected: bool,
+) {
+    let mut player = Hand::from_vector(
+        "player",
+        Strategy::ProbabilityTable,
+        vec![
+            Card {
+                suit: Suit::Clubs,
+                rank: first,
+            },
+            Card {
+                suit: Suit::Hearts,
+                rank: second,
+            },
+        ],
+    );
+    player.set_splits_remaining(splits_remaining);
+    assert_eq!(player.can_split(DEFAULT_BET_VALUE), expected);
+}
+
+/// Insurance is only offered against an Ace, on the opening two cards. The side bet is half the
+/// bet, rounded down, so a $1 bet can't be insured.
+#[rstest]
+#[case(Rank::Ace, "i\n", 2, 10, 5)]
+#[case(Rank::Ace, "i\n", 2, 1, 0)]
+#[case(Rank::Ace, "i\n", 2, 5, 2)]
+#[case(Rank::Ace, "n\n", 2, 10, 0)]
+#[case(Rank::King, "i\n", 2, 10, 0)]
+#[case(Rank::Ace, "i\n", 3, 10, 0)]
+fn check_offer_insurance(
+    #[case] up_card: Rank,
+    #[case] answer: &str,
+    #[case] num_cards: usize,
+    #[case] bet: isize,
+    #[case] expected: isize,
+) {
+    let mut human = Hand::from_vector("human", Strategy::Human, vec![]);
+    for _ in 0..num_cards {
+        human.add_card(Card {
+            suit: Suit::Clubs,
+            rank: Rank::Three,
+        });
+    }
+    assert_eq!(
+        human.offer_insurance(bet, up_card, &mut answer.as_bytes()),
+        expected
+    );
+    assert_eq!(human.get_credits(), HUMAN_DEFAULT_CREDITS - expected);
+}
+
+/// Even money is only offered on a natural against an Ace, and simulated players decline it
+#[rstest]
+#[case(Strategy::Human, Rank::Ace, Rank::King, "e\n", Some(true))]
+#[case(Strategy::Human, Rank::Ace, Rank::King, "n\n", Some(false))]
+#[case(Strategy::Human, Rank::Ten, Rank::King, "e\n", None)]
+#[case(Strategy::Human, Rank::Ace, Rank::Nine, "e\n", None)]
+#[case(Strategy::ProbabilityTable, Rank::Ace, Rank::King, "", Some(false))]
+fn check_offer_even_money(
+    #[case] strategy: Strategy,
+    #[case] up_card: Rank,
+    #[case] second_card: Rank,
+    #[case] answer: &str,
+    #[case] expected: Option<bool>,
+) {
+    let mut player = Hand::from_vector(
+        "player",
+        strategy,
+        vec![
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Ace,
+            },
+            Card {
+                suit: Suit::Clubs,
+                rank: second_card,
+            },
+        ],
+    );
+    assert_eq!(
+        player.offer_even_money(up_card, &mut answer.as_bytes()),
+        expected
+    );
+}
+
+/// A human may surrender their opening two cards only when the table allows it. Late surrender
+/// loses the whole bet to a dealer natural, early surrender does not.
+#[rstest]
+#[case(false, false, Rank::Seven, None)]
+#[case(true, false, Rank::Seven, Some(Outcome::Surrender))]
+#[case(true, false, Rank::Ace, Some(Outcome::Loss))]
+#[case(false, true, Rank::Ace, Some(Outcome::Surrender))]
+fn human_surrender(
+    #[case] late_surrender: bool,
+    #[case] early_surrender: bool,
+    #[case] dealer_hole: Rank,
+    #[case] expected: Option<Outcome>,
+) {
+    let mut deck = Deck::new();
+    let mut human = Hand::from_vector(
+        "human",
+        Strategy::Human,
+        vec![
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Ten,
+            },
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Six,
+            },
+        ],
+    );
+    human.set_rules(Rules {
+        late_surrender,
+        early_surrender,
+        ..Rules::default()
+    });
+    let dealer = Hand::from_vector(
+        "dealer",
+        Strategy::Dealer,
+        vec![
+            Card {
+                suit: Suit::Hearts,
+                rank: dealer_hole,
+            },
+            Card {
+                suit: Suit::Hearts,
+                rank: Rank::King,
+            },
+        ],
+    );
+    // Surrender isn't offered without the rule, so "r" is ignored.
+    let (stop, bet) = human.play_once_from(&mut deck, 10, Rank::King, &mut "r\n".as_bytes());
+    assert_eq!(bet, 10);
+    match expected {
+        Some(expected) => {
+            assert!(stop);
+            assert_eq!(Hand::determine_outcome(&human, &dealer), expected);
+        }
+        None => assert!(!stop && !human.surrendered),
+    }
+}
+
+/// Basic strategy surrenders a hard 16 against a 10 when allowed.
+#[rstest]
+fn probability_table_surrenders() {
+    let mut deck = Deck::new();
+    let mut player = Hand::from_vector(
+        "player",
+        Strategy::ProbabilityTable,
+        vec![
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Ten,
+            },
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Six,
+            },
+        ],
+    );
+    player.set_rules(Rules {
+        late_surrender: true,
+        ..Rules::default()
+    });
+    assert_eq!(player.play_once(&mut deck, 10, Rank::Ten), (true, 10));
+    assert!(player.surrendered);
+    assert_eq!(player.get_rules().settle(&Outcome::Surrender, 10), 5);
+}
+
+/// Basic strategy takes early surrender on the hands it would surrender late, but only when the
+/// table offers early surrender.
+#[rstest]
+#[case(true, Rank::Ten, true)]
+#[case(true, Rank::Five, false)]
+#[case(false, Rank::Ten, false)]
+fn probability_table_early_surrender(
+    #[case] early_surrender: bool,
+    #[case] up_card: Rank,
+    #[case] expected: bool,
+) {
+    let mut player = Hand::from_vector(
+        "player",
+        Strategy::ProbabilityTable,
+        vec![
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Ten,
+            },
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Six,
+            },
+        ],
+    );
+    player.set_rules(Rules {
+        early_surrender,
+        late_surrender: true,
+        ..Rules::default()
+    });
+    assert_eq!(
+        player.offer_early_surrender(up_card, &mut io::empty()),
+        expected
+    );
+    assert_eq!(player.is_surrendered(), expected);
+}
+
+/// Checking the deck is only allowed in practice mode, and doesn't change the hand or the deck.
+#[rstest]
+#[case(true)]
+#[case(false)]
+fn human_checks_deck(#[case] practice: bool) {
+    let mut deck = Deck::new();
+    let mut human = Hand::from_vector(
+        "human",
+        Strategy::Human,
+        vec![
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Ten,
+            },
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Six,
+            },
+        ],
+    );
+    human.set_practice(practice);
+    let before = deck.composition();
+    let (stop, _) = human.play_once_from(&mut deck, 10, Rank::King, &mut "deck\n".as_bytes());
+    assert!(!stop);
+    assert_eq!(human.cards.len(), 2);
+    assert_eq!(deck.composition(), before);
+}
+
+/// The dealer always stands on a hard 17, but only stands on a soft 17 when the table allows it.
+#[rstest]
+#[case(Rank::Ten, false, true)]
+#[case(Rank::Ten, true, true)]
+#[case(Rank::Ace, false, true)]
+#[case(Rank::Ace, true, false)]
+fn dealer_soft_17(
+    #[case] first_card: Rank,
+    #[case] dealer_hits_soft_17: bool,
+    #[case] expected_stop: bool,
+) {
+    let mut deck = Deck::new();
+    let mut dealer = Hand::from_vector(
+        "dealer",
+        Strategy::Dealer,
+        vec![
+            Card {
+                suit: Suit::Clubs,
+                rank: first_card,
+            },
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Six,
+            },
+        ],
+    );
+    // A Ten and a Six makes hard 16, so give that hand one more Ace for hard 17.
+    if first_card == Rank::Ten {
+        dealer.add_card(Card {
+            suit: Suit::Hearts,
+            rank: Rank::Ace,
+        });
+    }
+    dealer.set_rules(Rules {
+        dealer_hits_soft_17,
+        ..Rules::default()
+    });
+    let (stop, _) = dealer.play_once(&mut deck, NO_BET_VALUE, Rank::Six);
+    assert_eq!(stop, expected_stop);
+}
+
+/// The dealer stands on a soft 21 made from more than two cards
+#[rstest]
+fn dealer_stands_soft_21() {
+    let mut deck = Deck::new();
+    let mut dealer = Hand::from_vector(
+        "dealer",
+        Strategy::Dealer,
+        vec![
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Ace,
+            },
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Five,
+            },
+            Card {
+                suit: Suit::Hearts,
+                rank: Rank::Five,
+            },
+        ],
+    );
+    let (stop, _) = dealer.play_once(&mut deck, NO_BET_VALUE, Rank::Five);
+    assert!(stop);
+}
+
+/// The low total counts Aces as 1, the high total counts an Ace as 11 until it busts
+#[rstest]
+#[case(vec![Rank::Ace, Rank::Six], 7, 17, true)]
+#[case(vec![Rank::Ace, Rank::Six, Rank::Ten], 17, 27, false)]
+#[case(vec![Rank::Ace, Rank::Ace, Rank::Nine], 11, 21, true)]
+#[case(vec![Rank::Ten, Rank::Seven], 17, 17, false)]
+fn check_hand_value_accessors(
+    #[case] ranks: Vec<Rank>,
+    #[case] low: usize,
+    #[case] high: usize,
+    #[case] is_soft: bool,
+) {
+    let cards = ranks
+        .into_iter()
+        .map(|rank| Card {
+            suit: Suit::Spades,
+            rank,
+        })
+        .collect();
+    let value = Hand::from_vector("player", Strategy::ProbabilityTable, cards).value();
+    assert_eq!(value.low(), low);
+    assert_eq!(value.high(), high);
+    assert_eq!(value.is_soft(), is_soft);
+}
+
+/// Only one Ace of a multi-Ace hand is counted as 11, and never when it would bust the hand
+#[rstest]
+#[case(vec![Rank::Ace, Rank::Ace], 2, 12, 12)]
+#[case(vec![Rank::Ace, Rank::Ace, Rank::Ace], 3, 13, 13)]
+#[case(vec![Rank::Ace, Rank::Ace, Rank::Ace, Rank::Ace], 4, 14, 14)]
+#[case(vec![Rank::Ace, Rank::Ace, Rank::Ace, Rank::Eight], 11, 21, 21)]
+#[case(vec![Rank::Ace, Rank::Ace, Rank::Nine], 11, 21, 21)]
+#[case(vec![Rank::Nine, Rank::Ace, Rank::Ace], 11, 21, 21)]
+#[case(vec![Rank::Ace, Rank::Ace, Rank::Ten], 12, 22, 12)]
+#[case(vec![Rank::Ten, Rank::Ace, Rank::Ace], 12, 22, 12)]
+#[case(vec![Rank::Ace, Rank::Five, Rank::Ace, Rank::Five], 12, 22, 12)]
+#[case(vec![Rank::King, Rank::Ace, Rank::Queen, Rank::Ace], 22, 32, 22)]
+fn multi_ace_values(
+    #[case] ranks: Vec<Rank>,
+    #[case] low: usize,
+    #[case] high: usize,
+    #[case] final_value: usize,
+) {
+    let cards = ranks
+        .into_iter()
+        .map(|rank| Card {
+            suit: Suit::Hearts,
+            rank,
+        })
+        .collect();
+    let hand = Hand::from_vector("player", Strategy::ProbabilityTable, cards);
+    assert_eq!(hand.value().low(), low);
+    assert_eq!(hand.value().high(), high);
+    assert_eq!(hand.final_value(), final_value);
+}
+
+/// Basic strategy hits a soft 17 against a 7, but stands on a hard 17
+#[rstest]
+#[case(vec![Rank::Ace, Rank::Six], false)]
+#[case(vec![Rank::Ten, Rank::Six, Rank::Ace], true)]
+fn probability_table_soft_17(#[case] ranks: Vec<Rank>, #[case] expected_stop: bool) {
+    let mut deck = Deck::new();
+    let cards: Vec<Card> = ranks
+        .into_iter()
+        .map(|rank| Card {
+            suit: Suit::Clubs,
+            rank,
+        })
+        .collect();
+    let num_cards = cards.len();
+    let mut player = Hand::from_vector("player", Strategy::ProbabilityTable, cards);
+    let (stop, _) = player.play_once(&mut deck, 10, Rank::Seven);
+    assert_eq!(stop, expected_stop);
+    assert_eq!(player.cards.len(), num_cards + !expected_stop as usize);
+}
+
+/// The dealer only peeks when the up card could make a natural
+#[rstest]
+#[case(Rank::Ace, Rank::King, true)]
+#[case(Rank::Queen, Rank::Ace, true)]
+#[case(Rank::Ace, Rank::Nine, false)]
+#[case(Rank::Nine, Rank::Nine, false)]
+fn check_peek_for_blackjack(
+    #[case] up_card: Rank,
+    #[case] down_card: Rank,
+    #[case] expected: bool,
+) {
+    let mut cards = vec![
+        Card {
+            suit: Suit::Spades,
+            rank: down_card,
+        };
+        2
+    ];
+    cards[UP_CARD_IDX].rank = up_card;
+    let dealer = Hand::from_vector("Dealer", Strategy::Dealer, cards);
+    assert_eq!(dealer.get_up_card_rank(), up_card);
+    assert_eq!(dealer.peek_for_blackjack(), expected);
+}
+
+/// Hitting an empty deck is an error instead of a panic. The game recovers with a fresh deck.
+#[rstest]
+fn hit_empty_deck() {
+    let mut deck = Deck::from_cards(vec![]);
+    let mut player = Hand::new("player", Strategy::ProbabilityTable, HUMAN_DEFAULT_CREDITS);
+    assert_eq!(player.hit(&mut deck), Err(DeckEmpty));
+    assert!(player.get_cards().is_empty());
+
+    player.hit_or_reshuffle(&mut deck);
+    assert_eq!(player.get_cards().len(), 1);
+    assert_eq!(deck.remaining(), 51);
+}
+
+/// Ace counting edge cases, matching a hand of the same cards
+#[rstest]
+#[case(vec![], (0, 0))]
+#[case(vec![Rank::Ace], (1, 11))]
+#[case(vec![Rank::Ace, Rank::Ace], (2, 12))]
+#[case(vec![Rank::Ace, Rank::King], (11, 21))]
+#[case(vec![Rank::Nine, Rank::Five, Rank::Ace], (15, 15))]
+#[case(vec![Rank::Six, Rank::Ace, Rank::Nine], (16, 26))]
+#[case(vec![Rank::Ace, Rank::Ace, Rank::Ace, Rank::Eight], (11, 21))]
+fn check_evaluate_totals(#[case] ranks: Vec<Rank>, #[case] expected: (usize, usize)) {
+    assert_eq!(evaluate_totals(&ranks), expected);
+    let cards = ranks
+        .iter()
+        .map(|rank| Card {
+            suit: Suit::Spades,
+            rank: *rank,
+        })
+        .collect();
+    let value = Hand::from_vector("player", Strategy::ProbabilityTable, cards).value();
+    assert_eq!((value.low(), value.high()), expected);
+}
+
+/// Soft hands are annotated when rendered, hard hands are not
+#[rstest]
+#[case(vec![Rank::Ace, Rank::Six], true, "player (7/17) (soft) | $100")]
+#[case(vec![Rank::Ten, Rank::Seven], false, "player (17) | $100")]
+#[case(vec![Rank::Ace, Rank::Six, Rank::Ten], false, "player (17) | $100")]
+#[case(vec![Rank::Ace, Rank::King], true, "player (11/21) (soft) | $100")]
+fn check_soft_display(#[case] ranks: Vec<Rank>, #[case] soft: bool, #[case] header: &str) {
+    let cards = ranks
+        .iter()
+        .map(|rank| Card {
+            suit: Suit::Hearts,
+            rank: *rank,
+        })
+        .collect();
+    let hand = Hand::from_vector("player", Strategy::ProbabilityTable, cards);
+    assert_eq!(hand.is_soft(), soft);
+    assert_eq!(hand.to_string().lines().next(), Some(header));
+}
+
+/// Teaching mode explains the move with the hand's total and the dealer's up card, and the true count
+/// for card counters
+#[rstest]
+#[case(
+    Strategy::ProbabilityTable,
+    "16 vs 10: hit because the dealer is strong"
+)]
+#[case(
+    Strategy::CardCounter,
+    "16 vs 10: hit because the dealer is strong (true count -1.5)"
+)]
+fn explained_decision(#[case] strategy: Strategy, #[case] expected: &str) {
+    let ten = Card {
+        suit: Suit::Spades,
+        rank: Rank::Ten,
+    };
+    let six = Card {
+        suit: Suit::Hearts,
+        rank: Rank::Six,
+    };
+    let mut player = Hand::from_vector("player", strategy, vec![ten, six]);
+    player.set_true_count(-1.5);
+    assert_eq!(player.explanation(Rank::Ten, &Action::Hit), expected);
+}
+
+/// A freshly dealt hand holds its two cards, in the order they were dealt
+#[rstest]
+fn iter_dealt_cards() {
+    let mut deck = Deck::new();
+    let mut player = Hand::new("player", Strategy::ProbabilityTable, HUMAN_DEFAULT_CREDITS);
+    player.hit(&mut deck).unwrap();
+    player.hit(&mut deck).unwrap();
+    let cards: Vec<&Card> = player.iter_cards().collect();
+    assert_eq!(cards.len(), 2);
+    assert_eq!(cards, player.get_cards().iter().collect::<Vec<_>>());
+}
+
+/// A dealer who stands on 16 stops where the standard dealer draws, and one who stands on 18 draws
+/// to a 17
+#[rstest]
+#[case(16, vec![Rank::Ten, Rank::Six], true)]
+#[case(17, vec![Rank::Ten, Rank::Six], false)]
+#[case(17, vec![Rank::Ten, Rank::Seven], true)]
+#[case(18, vec![Rank::Ten, Rank::Seven], false)]
+#[case(18, vec![Rank::Ace, Rank::Seven], true)]
+fn dealer_stand_threshold(
+    #[case] dealer_stands: usize,
+    #[case] ranks: Vec<Rank>,
+    #[case] stands: bool,
+) {
+    let cards = ranks
+        .into_iter()
+        .map(|rank| Card {
+            suit: Suit::Clubs,
+            rank,
+        })
+        .collect();
+    let mut dealer = Hand::from_vector("dealer", Strategy::Dealer, cards);
+    dealer.set_rules(Rules {
+        dealer_stands,
+        ..Rules::default()
+    });
+    let mut deck = Deck::new();
+    assert_eq!(dealer.play_dealer(&mut deck), stands);
+    assert_eq!(dealer.get_cards().len(), if stands { 2 } else { 3 });
+}
+
+/// The probability table stands on any 21, and plays soft hands by their soft total
+#[rstest]
+#[case(vec![Rank::Ace, Rank::Five, Rank::Five], Rank::Six, true)]
+#[case(vec![Rank::Ten, Rank::Five, Rank::Six], Rank::Ten, true)]
+#[case(vec![Rank::Ace, Rank::Seven], Rank::Nine, false)]
+#[case(vec![Rank::Ace, Rank::Four, Rank::Three], Rank::Nine, false)]
+#[case(vec![Rank::Ace, Rank::Four, Rank::Three], Rank::Seven, true)]
+#[case(vec![Rank::Ace, Rank::Two, Rank::Four], Rank::Ten, false)]
+fn probability_table_soft_hands(
+    #[case] ranks: Vec<Rank>,
+    #[case] up_card: Rank,
+    #[case] stands: bool,
+) {
+    let cards: Vec<Card> = ranks
+        .into_iter()
+        .map(|rank| Card {
+            suit: Suit::Diamonds,
+            rank,
+        })
+        .collect();
+    let num_cards = cards.len();
+    let mut player = Hand::from_vector("player", Strategy::ProbabilityTable, cards);
+    let mut deck = Deck::new();
+    let (stop, _) = player.play_probability_table(&mut deck, NO_BET_VALUE, up_card);
+    assert_eq!(stop, stands);
+    assert_eq!(player.get_cards().len(), num_cards + usize::from(!stands));
+}
@@ -0,0 +1,103 @@
+//!
+//! File:           messages.rs
+//! Description:    Table of player-facing game text, to allow for translations
+//!
+
+use rstest::rstest;
+use std::collections::HashMap;
+
+use crate::types::hand::Outcome;
+
+/// Enumeration of every game message that can be customized
+#[derive(Hash, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum MessageKey {
+    BlackJack,
+    Bust,
+    DoubleDown,
+    OutOfMoney,
+    DealerTurn,
+    DealerBlackJack,
+    DealerReveal,
+    Win,
+    Loss,
+    Push,
+    Surrender,
+}
+
+/// Message table used when rendering game text. Defaults to English.
+#[derive(Clone)]
+pub struct Messages {
+    table: HashMap<MessageKey, String>,
+}
+
+impl Messages {
+    /// Constructs the default (English) message table
+    pub fn new() -> Self {
+        let mut messages = Messages {
+            table: HashMap::new(),
+        };
+        messages.set(MessageKey::BlackJack, "BlackJack!");
+        messages.set(MessageKey::Bust, "Bust!");
+        messages.set(MessageKey::DoubleDown, "Double down! (Neil would be proud)");
+        messages.set(
+            MessageKey::OutOfMoney,
+            "You're out of money! Good say, sir!",
+        );
+        messages.set(MessageKey::DealerTurn, "Dealer's Turn");
+        messages.set(MessageKey::DealerBlackJack, "Dealer has BlackJack!");
+        messages.set(MessageKey::DealerReveal, "The dealer flips the down card:");
+        messages.set(MessageKey::Win, "Winner!");
+        messages.set(MessageKey::Loss, "Loser!");
+        messages.set(MessageKey::Push, "Push.");
+        messages.set(MessageKey::Surrender, "Surrendered.");
+        messages
+    }
+
+    /// Overrides the text for a single message
+    pub fn set(&mut self, key: MessageKey, text: &str) {
+        self.table.insert(key, String::from(text));
+    }
+
+    /// Returns the text for a message
+    pub fn get(&self, key: MessageKey) -> &str {
+        self.table.get(&key).map_or("", |s| s.as_str())
+    }
+
+    /// Renders the banner shown at the end of a round
+    pub fn outcome_banner(&self, outcome: &Outcome) -> String {
+        let key = match outcome {
+            Outcome::Win => MessageKey::Win,
+            Outcome::Loss => MessageKey::Loss,
+            Outcome::Push => MessageKey::Push,
+            Outcome::BlackJack => MessageKey::BlackJack,
+            Outcome::Surrender => MessageKey::Surrender,
+        };
+        format!("----- {:^7} -----", self.get(key))
+    }
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Validates the default banners match the original game text
+#[rstest]
+#[case(Outcome::Win, "----- Winner! -----")]
+#[case(Outcome::Loss, "----- Loser!  -----")]
+#[case(Outcome::Push, "-----  Push.  -----")]
+fn default_outcome_banners(#[case] outcome: Outcome, #[case] expected: &str) {
+    assert_eq!(Messages::default().outcome_banner(&outcome), expected)
+}
+
+/// Supplying a custom message table changes the rendered text
+#[rstest]
+fn custom_win_message() {
+    let mut messages = Messages::default();
+    messages.set(MessageKey::Win, "Gagné !");
+    assert_eq!(
+        messages.outcome_banner(&Outcome::Win),
+        "----- Gagné ! -----"
+    )
+}
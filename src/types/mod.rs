@@ -1,4 +1,9 @@
+pub mod betting;
 pub mod card;
+pub mod counting;
 pub mod deck;
 pub mod hand;
+pub mod messages;
+pub mod round;
+pub mod rules;
 pub mod stats;
@@ -0,0 +1,185 @@
+//!
+//! File:           round.rs
+//! Description:    Plays out and settles a round of the game
+//!
+
+use rstest::rstest;
+#[cfg(test)]
+use std::io;
+use std::io::BufRead;
+
+use crate::types::card::Rank;
+#[cfg(test)]
+use crate::types::card::{Card, Suit};
+#[cfg(test)]
+use crate::types::counting::HiLoCounter;
+use crate::types::deck::Deck;
+use crate::types::hand::{Hand, Outcome};
+#[cfg(test)]
+use crate::types::hand::{Strategy, DEFAULT_BET_VALUE, HUMAN_DEFAULT_CREDITS, UP_CARD_IDX};
+
+/// Initialize a game between players and a dealer. Each player is dealt a card in turn, then the
+/// dealer, twice around the table.
+pub fn init_game(players: &mut [Hand], dealer: &mut Hand, deck: &mut Deck) {
+    for _ in 0..2 {
+        for player in players.iter_mut() {
+            player.hit_or_reshuffle(deck);
+        }
+        dealer.hit_or_reshuffle(deck);
+    }
+}
+
+/// Credits the player's winnings (including the returned stake) for a finished hand.
+pub fn pay_out(player: &mut Hand, outcome: &Outcome, final_bet: isize) {
+    let winnings = player.get_rules().settle(outcome, final_bet);
+    player.add_credits(winnings);
+}
+
+/// Determines the outcome of a finished hand and the bet it is settled for. Taking even money is paid
+/// as a win, whatever the dealer has.
+pub fn settle_hand(hand: &Hand, dealer: &Hand, bet: isize, even_money: bool) -> (Outcome, isize) {
+    if even_money {
+        return (Outcome::Win, bet);
+    }
+    (Hand::determine_outcome(hand, dealer), bet)
+}
+
+/// Offers the player insurance against a dealer Ace, settling it right away by peeking at the hole
+/// card. Returns the side bet taken (0 if none) and the credits handed back to the player.
+pub fn play_insurance<R: BufRead>(
+    player: &mut Hand,
+    dealer: &Hand,
+    bet: isize,
+    input: &mut R,
+) -> (isize, isize) {
+    let side_bet = player.offer_insurance(bet, dealer.get_up_card_rank(), input);
+    if side_bet <= 0 {
+        return (0, 0);
+    }
+    let returned = player
+        .get_rules()
+        .settle_insurance(side_bet, dealer.has_blackjack_with_hole());
+    player.add_credits(returned);
+    (side_bet, returned)
+}
+
+/// Plays out a player's turn, including every hand created by splitting pairs. Human players read
+/// their moves from `input` and `before_move` is called ahead of every move. While a hand is being
+/// played, it holds the player's credits, which are handed back to `player` at the end. Returns every
+/// played hand with its final bet (which accounts for a player doubling down).
+pub fn play_player_hands<R: BufRead, F: FnMut(&Hand)>(
+    player: &mut Hand,
+    deck: &mut Deck,
+    bet: isize,
+    up_card: Rank,
+    input: &mut R,
+    mut before_move: F,
+) -> Vec<(Hand, isize)> {
+    let max_splits = player.get_rules().max_splits;
+    let mut hands = vec![(player.clone(), bet)];
+    player.sub_credits(player.get_credits());
+
+    let mut cur = 0;
+    while cur < hands.len() {
+        loop {
+            let splits_remaining = max_splits.saturating_sub(hands.len() - 1);
+            let (hand, hand_bet) = &mut hands[cur];
+            hand.set_splits_remaining(splits_remaining);
+            before_move(hand);
+            let (stop, new_bet) = hand.play_once_from(deck, *hand_bet, up_card, input);
+            *hand_bet = new_bet;
+            if let Some(split_hand) = hand.take_split() {
+                hands.push((split_hand, bet));
+            }
+            if stop {
+                break;
+            }
+        }
+
+        // Pass the credits along to the next hand, or back to the player
+        let credits = hands[cur].0.get_credits();
+        hands[cur].0.sub_credits(credits);
+        match hands.get_mut(cur + 1) {
+            Some((next_hand, _)) => next_hand.add_credits(credits),
+            None => player.add_credits(credits),
+        }
+        cur += 1;
+    }
+    hands
+}
+
+/// Splitting creates extra hands, each with its own bet, and the player's credits are conserved.
+#[rstest]
+fn split_hands_are_played_separately() {
+    let mut deck = Deck::new();
+    let mut player = Hand::from_vector(
+        "Auto Player",
+        Strategy::ProbabilityTable,
+        vec![
+            Card {
+                suit: Suit::Clubs,
+                rank: Rank::Ace,
+            },
+            Card {
+                suit: Suit::Hearts,
+                rank: Rank::Ace,
+            },
+        ],
+    );
+    player.sub_credits(DEFAULT_BET_VALUE);
+    let hands = play_player_hands(
+        &mut player,
+        &mut deck,
+        DEFAULT_BET_VALUE,
+        Rank::Six,
+        &mut io::empty(),
+        |_| (),
+    );
+    assert!(hands.len() >= 2);
+    let wagered: isize = hands.iter().map(|(_, bet)| bet).sum();
+    assert_eq!(player.get_credits() + wagered, HUMAN_DEFAULT_CREDITS);
+    assert!(hands.iter().all(|(hand, _)| hand.get_credits() == 0));
+}
+
+/// A card counter only insures against a dealer Ace when the low cards are gone and the rest of the
+/// shoe is rich in tens
+#[rstest]
+#[case(Rank::Two, 10, (5, 15))]
+#[case(Rank::Two, 1, (0, 0))]
+#[case(Rank::King, 10, (0, 0))]
+fn counter_insures_ten_rich_shoe(
+    #[case] seen: Rank,
+    #[case] bet: isize,
+    #[case] expected: (isize, isize),
+) {
+    let card = |rank| Card {
+        suit: Suit::Spades,
+        rank,
+    };
+    let mut cards = vec![card(Rank::King); 20];
+    cards.extend(vec![card(seen); 10]);
+    let mut deck = Deck::from_cards(cards);
+    for _ in 0..10 {
+        deck.deal();
+    }
+    let mut counter = HiLoCounter::new();
+    counter.count_dealt(&deck);
+
+    let mut player = Hand::from_vector(
+        "Auto Player",
+        Strategy::CardCounter,
+        vec![card(Rank::Nine), card(Rank::Seven)],
+    );
+    let dealer = Hand::from_vector(
+        "Dealer",
+        Strategy::Dealer,
+        vec![card(Rank::King), card(Rank::Ace)],
+    );
+    let mut visible = player.get_cards().to_vec();
+    visible.push(dealer.get_cards()[UP_CARD_IDX]);
+    player.set_true_count(counter.true_count(&deck, &visible));
+    assert_eq!(
+        play_insurance(&mut player, &dealer, bet, &mut io::empty()),
+        expected
+    );
+}
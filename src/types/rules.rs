@@ -0,0 +1,411 @@
+//!
+//! File:           rules.rs
+//! Description:    Table rules that vary from casino to casino
+//!
+
+use rstest::rstest;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::types::hand::{Outcome, DEALER_HAND_THRESHOLD};
+
+/// Describes how fractional payouts (i.e. 3:2 on an odd bet) are rounded to whole credits
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Round down, as casinos do
+    Floor,
+    /// Round to the nearest credit, with halves rounding up
+    Nearest,
+}
+
+/// A payout ratio, i.e. 2:1 pays 2 credits for every 1 credit wagered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Payout {
+    pub numerator: isize,
+    pub denominator: isize,
+}
+
+impl Payout {
+    pub const fn new(numerator: isize, denominator: isize) -> Self {
+        Payout {
+            numerator,
+            denominator,
+        }
+    }
+
+    /// Winnings paid on a bet at this ratio, rounded to whole credits. Does not include the original
+    /// stake.
+    pub fn winnings(&self, bet: isize, rounding: RoundingMode) -> isize {
+        match rounding {
+            RoundingMode::Floor => (bet * self.numerator).div_euclid(self.denominator),
+            RoundingMode::Nearest => {
+                (2 * bet * self.numerator + self.denominator).div_euclid(2 * self.denominator)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Payout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.numerator, self.denominator)
+    }
+}
+
+impl FromStr for Payout {
+    type Err = String;
+
+    /// Parses a ratio like "3:2" or "6:5". Both sides must be positive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |part: &str| part.trim().parse::<isize>().ok().filter(|n| *n > 0);
+        match s.split_once(':').map(|(n, d)| (parse(n), parse(d))) {
+            Some((Some(numerator), Some(denominator))) => Ok(Payout::new(numerator, denominator)),
+            _ => Err(format!(
+                "`{}` is not a payout ratio (i.e. 3:2, 6:5 or 1:1)",
+                s
+            )),
+        }
+    }
+}
+
+/// Standard payout for a natural
+pub const DEFAULT_BLACKJACK_PAYOUT: Payout = Payout::new(3, 2);
+/// Standard insurance payout
+pub const DEFAULT_INSURANCE_PAYOUT: Payout = Payout::new(2, 1);
+
+/// Names of the rule presets known to `Rules::preset()`
+pub const RULES_PRESETS: [&str; 3] = ["vegas-strip", "atlantic-city", "european"];
+
+/// By default, a player may split up to 4 hands
+pub const DEFAULT_MAX_SPLITS: usize = 3;
+
+/// Describes how a tie between the player and the dealer is settled
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PushRule {
+    /// Ties are a push, the player's bet is returned
+    Standard,
+    /// Ties go to the dealer
+    DealerWinsTies,
+    /// Promotional rule where ties go to the player. Pays as a normal win.
+    PlayerWinsTies,
+}
+
+/// Describes the configurable rules of a table
+#[derive(Clone, Copy, Debug)]
+pub struct Rules {
+    /// Payout ratio for a winning natural
+    pub blackjack_pays: Payout,
+    /// Payout ratio for a winning insurance side bet
+    pub insurance_pays: Payout,
+    /// Allows surrendering half the bet after the dealer peeks for BlackJack
+    pub late_surrender: bool,
+    /// Allows surrendering half the bet before the dealer peeks for BlackJack
+    pub early_surrender: bool,
+    /// How ties are settled
+    pub push_rule: PushRule,
+    /// Maximum number of times a player may split (and re-split) in a single round
+    pub max_splits: usize,
+    /// How fractional payouts are rounded
+    pub payout_rounding: RoundingMode,
+    /// The dealer hits a soft 17 (an Ace counted as 11), instead of standing on all 17s
+    pub dealer_hits_soft_17: bool,
+    /// Total the dealer stands on. EV estimates always assume the standard 17.
+    pub dealer_stands: usize,
+    /// The dealer peeks for BlackJack when showing an Ace or a ten-value card (US rules). Without
+    /// the peek (European rules), a dealer natural also takes any extra wagers from doubles and splits.
+    pub dealer_peeks: bool,
+    /// Allows doubling down on the hands made by splitting a pair
+    pub das: bool,
+    /// Number of decks in the table's shoe, or None to deal from however many decks the game uses
+    pub decks: Option<usize>,
+}
+
+impl Rules {
+    /// Constructs the standard set of rules
+    pub fn new() -> Self {
+        Rules {
+            blackjack_pays: DEFAULT_BLACKJACK_PAYOUT,
+            insurance_pays: DEFAULT_INSURANCE_PAYOUT,
+            late_surrender: false,
+            early_surrender: false,
+            push_rule: PushRule::Standard,
+            max_splits: DEFAULT_MAX_SPLITS,
+            payout_rounding: RoundingMode::Floor,
+            dealer_hits_soft_17: false,
+            dealer_stands: DEALER_HAND_THRESHOLD,
+            dealer_peeks: true,
+            das: true,
+            decks: None,
+        }
+    }
+
+    /// Constructs a named set of common casino rules, or None if `name` isn't one of `RULES_PRESETS`.
+    /// Every preset pays 3:2 on a natural and 2:1 on insurance, and the dealer stands on all 17s:
+    /// - "vegas-strip": a 4 deck shoe, the dealer peeks, doubling after splitting is allowed, no
+    ///   surrender and a pair may be split up to 4 hands.
+    /// - "atlantic-city": like "vegas-strip", but from an 8 deck shoe and with late surrender.
+    /// - "european": a 6 deck shoe, the dealer takes no hole card (so doesn't peek), doubling after
+    ///   splitting is allowed, no surrender and a pair may only be split once.
+    pub fn preset(name: &str) -> Option<Self> {
+        let vegas_strip = Rules {
+            late_surrender: false,
+            dealer_hits_soft_17: false,
+            dealer_peeks: true,
+            das: true,
+            max_splits: DEFAULT_MAX_SPLITS,
+            decks: Some(4),
+            ..Rules::new()
+        };
+        match name {
+            "vegas-strip" => Some(vegas_strip),
+            "atlantic-city" => Some(Rules {
+                late_surrender: true,
+                decks: Some(8),
+                ..vegas_strip
+            }),
+            "european" => Some(Rules {
+                dealer_peeks: false,
+                max_splits: 1,
+                decks: Some(6),
+                ..vegas_strip
+            }),
+            _ => None,
+        }
+    }
+
+    /// Settles a finished hand. Returns the credits handed back to the player: the stake plus any
+    /// winnings. Fractional natural payouts (i.e. 3:2 on $5 pays $7.50) are rounded according to
+    /// `payout_rounding`, which defaults to rounding down like a casino.
+    pub fn settle(&self, outcome: &Outcome, bet: isize) -> isize {
+        match outcome {
+            Outcome::Win => 2 * bet,
+            Outcome::BlackJack => bet + self.blackjack_pays.winnings(bet, self.payout_rounding),
+            Outcome::Push => bet,
+            Outcome::Loss => 0,
+            Outcome::Surrender => bet / 2,
+        }
+    }
+
+    /// Settles an insurance side bet. Returns the credits handed back to the player: the stake plus
+    /// winnings if the dealer has a natural, nothing otherwise.
+    pub fn settle_insurance(&self, side_bet: isize, dealer_natural: bool) -> isize {
+        if !dealer_natural {
+            return 0;
+        }
+        side_bet + self.insurance_pays.winnings(side_bet, self.payout_rounding)
+    }
+}
+
+/// Approximate house edge (as a percent of the bet) of basic strategy with standard rules and 6 decks
+pub const BASE_HOUSE_EDGE: f64 = 0.40;
+/// Number of decks the base house edge assumes
+const BASE_HOUSE_EDGE_DECKS: usize = 6;
+/// Approximate fraction of hands that tie with the dealer
+const APPROX_PUSH_RATE: f64 = 0.085;
+/// Approximate fraction of hands that are a player natural
+const APPROX_NATURAL_RATE: f64 = 0.0475;
+/// Approximate fraction of hands that basic strategy doubles down on
+const APPROX_DOUBLE_RATE: f64 = 0.095;
+/// Approximate fraction of doubled hands that win
+const APPROX_DOUBLE_WIN_RATE: f64 = 0.56;
+
+/// Change in house edge (percent) from dealing with `decks` decks instead of 6. Fewer decks favor the
+/// player. Figures are the commonly published basic strategy approximations.
+fn deck_count_adjustment(decks: usize) -> f64 {
+    match decks {
+        0 | 1 => -0.46,
+        2 => -0.17,
+        3 => -0.08,
+        4 => -0.04,
+        5 => -0.01,
+        BASE_HOUSE_EDGE_DECKS => 0.0,
+        7 => 0.01,
+        _ => 0.02,
+    }
+}
+
+/// Itemized estimate of the basic strategy house edge (percent of the bet) under `rules` with `decks`
+/// decks. Each entry describes one adjustment to the base edge.
+pub fn edge_breakdown(decks: usize, rules: &Rules) -> Vec<(String, f64)> {
+    let mut breakdown = vec![
+        (
+            format!("Base ({} decks)", BASE_HOUSE_EDGE_DECKS),
+            BASE_HOUSE_EDGE,
+        ),
+        (format!("{} deck(s)", decks), deck_count_adjustment(decks)),
+    ];
+    if rules.early_surrender {
+        breakdown.push((String::from("Early surrender"), -0.63));
+    } else if rules.late_surrender {
+        breakdown.push((String::from("Late surrender"), -0.08));
+    }
+    if rules.dealer_hits_soft_17 {
+        breakdown.push((String::from("Dealer hits soft 17"), 0.22));
+    }
+    if !rules.das {
+        breakdown.push((String::from("No double after split"), 0.14));
+    }
+    if !rules.dealer_peeks {
+        breakdown.push((String::from("No dealer peek"), 0.11));
+    }
+    match rules.push_rule {
+        PushRule::Standard => (),
+        PushRule::DealerWinsTies => {
+            breakdown.push((String::from("Dealer wins ties"), 100.0 * APPROX_PUSH_RATE))
+        }
+        PushRule::PlayerWinsTies => {
+            breakdown.push((String::from("Player wins ties"), -100.0 * APPROX_PUSH_RATE))
+        }
+    }
+    breakdown
+}
+
+/// Estimates the basic strategy house edge (percent of the bet) when `base_rules` are dealt from a
+/// shoe of `decks` decks.
+pub fn house_edge_by_decks(decks: usize, base_rules: &Rules) -> f64 {
+    edge_breakdown(decks, base_rules)
+        .iter()
+        .map(|(_, edge)| edge)
+        .sum()
+}
+
+/// Fraction of decided (not pushed) hands a player has to win to break even under the payouts of
+/// `rules`. Naturals pay a bonus and doubled hands risk twice the bet, so this is a bit under 50%.
+pub fn break_even_win_rate(rules: &Rules) -> f64 {
+    let decided = 1.0 - APPROX_PUSH_RATE;
+    let natural_share = APPROX_NATURAL_RATE / decided;
+    let double_win_share = APPROX_DOUBLE_RATE * APPROX_DOUBLE_WIN_RATE / decided;
+    let double_loss_share = APPROX_DOUBLE_RATE * (1.0 - APPROX_DOUBLE_WIN_RATE) / decided;
+    let natural_pays =
+        rules.blackjack_pays.numerator as f64 / rules.blackjack_pays.denominator as f64;
+    // Break even when the wins (1 unit, a natural's payout, or 2 units when doubled) match the losses
+    // (1 unit, or 2 units when doubled).
+    (1.0 + natural_share * (1.0 - natural_pays) - double_win_share + double_loss_share) / 2.0
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Every preset is known by name, and differs from the others
+#[rstest]
+#[case("vegas-strip", false, true, DEFAULT_MAX_SPLITS, 4)]
+#[case("atlantic-city", true, true, DEFAULT_MAX_SPLITS, 8)]
+#[case("european", false, false, 1, 6)]
+fn check_preset(
+    #[case] name: &str,
+    #[case] late_surrender: bool,
+    #[case] dealer_peeks: bool,
+    #[case] max_splits: usize,
+    #[case] decks: usize,
+) {
+    assert!(RULES_PRESETS.contains(&name));
+    let rules = Rules::preset(name).unwrap();
+    assert_eq!(rules.late_surrender, late_surrender);
+    assert_eq!(rules.dealer_peeks, dealer_peeks);
+    assert_eq!(rules.max_splits, max_splits);
+    assert_eq!(rules.decks, Some(decks));
+    assert!(rules.das && !rules.dealer_hits_soft_17);
+    assert_eq!(rules.blackjack_pays, DEFAULT_BLACKJACK_PAYOUT);
+}
+
+/// Unknown presets aren't guessed at
+#[rstest]
+fn unknown_preset() {
+    assert!(Rules::preset("monte-carlo").is_none());
+    assert!(Rules::preset("Vegas-Strip").is_none());
+}
+
+/// Validates hand settlement. Naturals pay 3:2 by default.
+#[rstest]
+#[case(Outcome::BlackJack, 10, 25)]
+#[case(Outcome::Win, 10, 20)]
+#[case(Outcome::Push, 10, 10)]
+#[case(Outcome::Loss, 10, 0)]
+fn check_settlement(#[case] outcome: Outcome, #[case] bet: isize, #[case] expected: isize) {
+    assert_eq!(Rules::default().settle(&outcome, bet), expected)
+}
+
+/// Validates rounding of fractional payouts
+#[rstest]
+#[case(Payout::new(6, 5), 5, RoundingMode::Floor, 6)]
+#[case(Payout::new(6, 5), 7, RoundingMode::Floor, 8)]
+#[case(Payout::new(6, 5), 7, RoundingMode::Nearest, 8)]
+#[case(Payout::new(6, 5), 9, RoundingMode::Floor, 10)]
+#[case(Payout::new(6, 5), 9, RoundingMode::Nearest, 11)]
+#[case(Payout::new(3, 2), 5, RoundingMode::Floor, 7)]
+#[case(Payout::new(3, 2), 5, RoundingMode::Nearest, 8)]
+#[case(Payout::new(3, 2), 1, RoundingMode::Floor, 1)]
+fn check_payout_rounding(
+    #[case] payout: Payout,
+    #[case] bet: isize,
+    #[case] rounding: RoundingMode,
+    #[case] expected: isize,
+) {
+    assert_eq!(payout.winnings(bet, rounding), expected)
+}
+
+/// A $10 natural wins $15 at 3:2, but only $12 at 6:5
+#[rstest]
+#[case("3:2", 25)]
+#[case("6:5", 22)]
+#[case("1:1", 20)]
+fn check_blackjack_pays(#[case] ratio: &str, #[case] expected: isize) {
+    let rules = Rules {
+        blackjack_pays: ratio.parse().unwrap(),
+        ..Rules::default()
+    };
+    assert_eq!(rules.settle(&Outcome::BlackJack, 10), expected);
+    assert_eq!(rules.blackjack_pays.to_string(), ratio);
+}
+
+/// Payout ratios need a positive numerator and denominator
+#[rstest]
+#[case("3-2")]
+#[case("3:0")]
+#[case("-6:5")]
+#[case("three:two")]
+fn invalid_payout(#[case] ratio: &str) {
+    assert!(ratio.parse::<Payout>().is_err());
+}
+
+/// Validates insurance settlement at the standard and a non-standard payout
+#[rstest]
+#[case(DEFAULT_INSURANCE_PAYOUT, 5, true, 15)]
+#[case(DEFAULT_INSURANCE_PAYOUT, 5, false, 0)]
+#[case(Payout::new(3, 1), 5, true, 20)]
+#[case(Payout::new(3, 1), 5, false, 0)]
+fn check_insurance_settlement(
+    #[case] insurance_pays: Payout,
+    #[case] side_bet: isize,
+    #[case] dealer_natural: bool,
+    #[case] expected: isize,
+) {
+    let rules = Rules {
+        insurance_pays,
+        ..Rules::default()
+    };
+    assert_eq!(rules.settle_insurance(side_bet, dealer_natural), expected)
+}
+
+/// Fewer decks lower the house edge
+#[rstest]
+fn single_deck_edge() {
+    let rules = Rules::default();
+    assert!(house_edge_by_decks(1, &rules) < house_edge_by_decks(6, &rules));
+    assert!(house_edge_by_decks(6, &rules) < house_edge_by_decks(8, &rules));
+    assert_eq!(house_edge_by_decks(6, &rules), BASE_HOUSE_EDGE);
+}
+
+/// A 6:5 natural needs a higher win rate to break even than 3:2
+#[rstest]
+fn check_break_even_win_rate() {
+    let three_to_two = break_even_win_rate(&Rules::default());
+    let six_to_five = break_even_win_rate(&Rules {
+        blackjack_pays: Payout::new(6, 5),
+        ..Rules::default()
+    });
+    assert!(three_to_two < 0.5);
+    assert!(six_to_five > three_to_two);
+}
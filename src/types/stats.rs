@@ -5,17 +5,82 @@
 //!
 //!
 
+use rstest::rstest;
 use std::fmt;
 
+use crate::data::mistakes::{Decision, MistakeReport};
+use crate::types::card::MAX_BLACKJACK;
 use crate::types::hand::Outcome;
+use crate::types::rules::Rules;
+
+/// How a session (run) ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub enum SessionEnd {
+    /// Every game of the session was played
+    OutOfHands,
+    /// The player went broke
+    Busted,
+    /// The player walked away after reaching their target
+    Target,
+    /// The player walked away after losing down to their stop-loss
+    StopLoss,
+}
 
 /// Data to track per player "run" (how long a player sits at the table)
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
 pub struct RunStats {
     num_games: usize,
     wins: usize,
     losses: usize,
     pushes: usize,
+    /// Games the player surrendered, which are also counted as losses
+    surrenders: usize,
+    /// Rounds the player split into several hands
+    split_rounds: usize,
+    /// Hands played in split rounds, each of which is also counted as a game
+    split_hands: usize,
     remaining_credits: isize,
+    /// Most credits the player had at any point of the run
+    peak_credits: Option<isize>,
+    /// Fewest credits the player had at any point of the run
+    low_credits: Option<isize>,
+    /// Why the run ended, which is before every game was played if the player went broke or walked away
+    session_end: SessionEnd,
+    /// Cumulative net units (1 unit per game) after each game, for streak analysis.
+    net_units: Vec<isize>,
+    insurance_wins: usize,
+    insurance_losses: usize,
+    /// Net credits won (or lost) on insurance side bets
+    insurance_net: isize,
+    /// Hands the dealer played out, after the player acted
+    dealer_hands: usize,
+    /// Dealer hands that went over 21
+    dealer_busts: usize,
+    /// Times even money was offered on a natural against a dealer Ace
+    even_money_offers: usize,
+    /// Times even money was taken
+    even_money_taken: usize,
+    /// Largest bet placed
+    max_bet: isize,
+    /// Bets the betting strategy wanted to raise past the credits available
+    capped_bets: usize,
+    /// Bets the betting strategy wanted to raise past the table maximum
+    table_max_caps: usize,
+    /// Longest Monte Carlo betting sequence reached
+    longest_sequence: usize,
+    /// Games in the current streak: positive for wins, negative for losses
+    #[cfg_attr(feature = "json", serde(skip))]
+    current_streak: isize,
+    /// Pushes end a streak, instead of being skipped over
+    #[cfg_attr(feature = "json", serde(skip))]
+    pushes_end_streaks: bool,
+    longest_win_streak: usize,
+    longest_loss_streak: usize,
+    /// Decisions that deviated from the probability table
+    #[cfg_attr(feature = "json", serde(skip))]
+    mistakes: MistakeReport,
 }
 
 impl RunStats {
@@ -25,17 +90,180 @@ impl RunStats {
             wins: 0,
             losses: 0,
             pushes: 0,
+            surrenders: 0,
+            split_rounds: 0,
+            split_hands: 0,
             remaining_credits: 0,
+            peak_credits: None,
+            low_credits: None,
+            session_end: SessionEnd::OutOfHands,
+            net_units: Vec::new(),
+            insurance_wins: 0,
+            insurance_losses: 0,
+            insurance_net: 0,
+            dealer_hands: 0,
+            dealer_busts: 0,
+            even_money_offers: 0,
+            even_money_taken: 0,
+            max_bet: 0,
+            capped_bets: 0,
+            table_max_caps: 0,
+            longest_sequence: 0,
+            current_streak: 0,
+            pushes_end_streaks: false,
+            longest_win_streak: 0,
+            longest_loss_streak: 0,
+            mistakes: MistakeReport::new(),
+        }
+    }
+
+    /// Sets whether a push ends a win or loss streak. By default, pushes don't affect streaks.
+    pub fn set_pushes_end_streaks(&mut self, pushes_end_streaks: bool) {
+        self.pushes_end_streaks = pushes_end_streaks;
+    }
+
+    /// Audits the decisions a player made against the probability table
+    pub fn record_decisions(&mut self, decisions: &[Decision], rules: &Rules) {
+        for decision in decisions {
+            self.mistakes.record(decision, rules);
         }
     }
 
     /// Records stats when a game (single match) ends
     pub fn record_match_end(&mut self, outcome: Outcome) {
         self.num_games += 1;
-        match outcome {
-            Outcome::Win => self.wins += 1,
-            Outcome::Loss => self.losses += 1,
-            Outcome::Push => self.pushes += 1,
+        let delta = match outcome {
+            // The natural's bonus is ignored, it counts as 1 unit.
+            Outcome::Win | Outcome::BlackJack => {
+                self.wins += 1;
+                1
+            }
+            Outcome::Loss => {
+                self.losses += 1;
+                -1
+            }
+            // A surrender is also counted as a loss.
+            Outcome::Surrender => {
+                self.losses += 1;
+                self.surrenders += 1;
+                -1
+            }
+            Outcome::Push => {
+                self.pushes += 1;
+                0
+            }
+        };
+        let last = self.net_units.last().copied().unwrap_or(0);
+        self.net_units.push(last + delta);
+        self.record_streak(delta);
+    }
+
+    /// Records the hands of a round the player split. Every hand is settled for its own bet against the
+    /// dealer, so each one counts as its own game (and streak step), in the order they were played.
+    /// The round is also tallied as a split, so split hands can be told apart from the rest.
+    pub fn record_split_result(&mut self, outcomes: &[Outcome]) {
+        for outcome in outcomes {
+            self.record_match_end(*outcome);
+        }
+        self.split_rounds += 1;
+        self.split_hands += outcomes.len();
+    }
+
+    /// Extends the current streak with a game that won (`delta` 1), lost (-1) or pushed (0)
+    fn record_streak(&mut self, delta: isize) {
+        self.current_streak = match delta {
+            0 if self.pushes_end_streaks => 0,
+            0 => self.current_streak,
+            1 => self.current_streak.max(0) + 1,
+            _ => self.current_streak.min(0) - 1,
+        };
+        if self.current_streak > 0 {
+            self.longest_win_streak = self.longest_win_streak.max(self.current_streak as usize);
+        } else {
+            self.longest_loss_streak = self
+                .longest_loss_streak
+                .max(self.current_streak.unsigned_abs());
+        }
+    }
+
+    /// Most games won in a row
+    pub fn longest_win_streak(&self) -> usize {
+        self.longest_win_streak
+    }
+
+    /// Most games lost in a row
+    pub fn longest_loss_streak(&self) -> usize {
+        self.longest_loss_streak
+    }
+
+    /// Number of games played
+    pub fn num_games(&self) -> usize {
+        self.num_games
+    }
+
+    /// Number of games won
+    pub fn wins(&self) -> usize {
+        self.wins
+    }
+
+    /// Number of games lost
+    pub fn losses(&self) -> usize {
+        self.losses
+    }
+
+    /// Number of games pushed
+    pub fn pushes(&self) -> usize {
+        self.pushes
+    }
+
+    /// Number of games surrendered
+    pub fn surrenders(&self) -> usize {
+        self.surrenders
+    }
+
+    /// Decisions audited against the probability table, and how they deviated from it
+    pub fn mistakes(&self) -> &MistakeReport {
+        &self.mistakes
+    }
+
+    /// Number of rounds the player split
+    pub fn split_rounds(&self) -> usize {
+        self.split_rounds
+    }
+
+    /// Credits the player walked away with
+    pub fn remaining_credits(&self) -> isize {
+        self.remaining_credits
+    }
+
+    /// Cumulative net units after each game, assuming a flat 1 unit bet.
+    pub fn net_units(&self) -> &[isize] {
+        &self.net_units
+    }
+
+    /// Records the result of an insurance side bet, given the credits handed back to the player
+    pub fn record_insurance(&mut self, side_bet: isize, returned: isize) {
+        if returned > 0 {
+            self.insurance_wins += 1;
+        } else {
+            self.insurance_losses += 1;
+        }
+        self.insurance_net += returned - side_bet;
+    }
+
+    /// Records a hand the dealer played out, ending with a `final_value`
+    pub fn record_dealer_hand(&mut self, final_value: usize) {
+        self.dealer_hands += 1;
+        if final_value > MAX_BLACKJACK {
+            self.dealer_busts += 1;
+        }
+    }
+
+    /// Records an even money offer, and whether it was taken
+    pub fn record_even_money(&mut self, taken: bool) {
+        self.even_money_offers += 1;
+        if taken {
+            self.even_money_taken += 1;
         }
     }
 
@@ -43,15 +271,98 @@ impl RunStats {
     pub fn record_credits(&mut self, credits: isize) {
         self.remaining_credits = credits;
     }
+
+    /// Records a bet placed, keeping the largest
+    pub fn record_bet(&mut self, bet: isize) {
+        self.max_bet = self.max_bet.max(bet);
+    }
+
+    /// Largest bet placed during the run
+    pub fn max_bet(&self) -> isize {
+        self.max_bet
+    }
+
+    /// Records a bet that was capped to the credits available
+    pub fn record_capped_bet(&mut self) {
+        self.capped_bets += 1;
+    }
+
+    /// Records a bet that was lowered to the table maximum
+    pub fn record_table_max_cap(&mut self) {
+        self.table_max_caps += 1;
+    }
+
+    /// Number of bets lowered to the table maximum
+    pub fn table_max_caps(&self) -> usize {
+        self.table_max_caps
+    }
+
+    /// Records the length of the player's betting sequence, keeping the longest
+    pub fn record_sequence_length(&mut self, length: usize) {
+        self.longest_sequence = self.longest_sequence.max(length);
+    }
+
+    /// Longest betting sequence reached during the run
+    pub fn longest_sequence(&self) -> usize {
+        self.longest_sequence
+    }
+
+    /// Records that the player went broke, ending the run early
+    pub fn record_bust(&mut self) {
+        self.session_end = SessionEnd::Busted;
+    }
+
+    /// Returns true if the player went broke during the run
+    pub fn is_busted(&self) -> bool {
+        self.session_end == SessionEnd::Busted
+    }
+
+    /// Records why the run ended, when it ended early
+    pub fn record_session_end(&mut self, session_end: SessionEnd) {
+        self.session_end = session_end;
+    }
+
+    /// Why the run ended
+    pub fn session_end(&self) -> SessionEnd {
+        self.session_end
+    }
+
+    /// Records the player's credits at some point of the run (i.e. after each hand), tracking the
+    /// highs and lows of their bankroll
+    pub fn record_credit_snapshot(&mut self, credits: isize) {
+        self.peak_credits = Some(self.peak_credits.map_or(credits, |peak| peak.max(credits)));
+        self.low_credits = Some(self.low_credits.map_or(credits, |low| low.min(credits)));
+    }
+
+    /// Most credits the player had during the run, if any snapshots were recorded
+    pub fn peak_credits(&self) -> Option<isize> {
+        self.peak_credits
+    }
+
+    /// Fewest credits the player had during the run, if any snapshots were recorded
+    pub fn low_credits(&self) -> Option<isize> {
+        self.low_credits
+    }
+}
+
+impl Default for RunStats {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl fmt::Display for RunStats {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "Games: {} | W/L/P: {}/{}/{} | Credits: ${}",
-            self.num_games, self.wins, self.losses, self.pushes, self.remaining_credits
+            "Games: {} | W/L/P: {}/{}/{}",
+            self.num_games, self.wins, self.losses, self.pushes
         )
+        .expect("I/O Error");
+        if self.surrenders > 0 {
+            write!(f, " | Surrenders: {}", self.surrenders).expect("I/O Error");
+        }
+        write!(f, " | Credits: ${}", self.remaining_credits)
     }
 }
 
@@ -63,35 +374,215 @@ pub struct TotalRunStats {
     wins: usize,
     losses: usize,
     pushes: usize,
+    surrenders: usize,
+    split_rounds: usize,
+    split_hands: usize,
     total_credits: isize,
+    /// Sum of the squares of each run's remaining credits, for the variance
+    total_credits_squared: f64,
+    /// Sum of each run's peak credits, falling back to its remaining credits
+    total_peak_credits: isize,
+    /// Sum of each run's lowest credits, falling back to its remaining credits
+    total_low_credits: isize,
     num_walk_away_with_more: usize,
+    /// Runs where the player went broke
+    num_busted_out: usize,
+    /// Runs where the player walked away at their target
+    num_hit_target: usize,
+    /// Runs where the player walked away at their stop-loss
+    num_stop_loss: usize,
+    insurance_wins: usize,
+    insurance_losses: usize,
+    insurance_net: isize,
+    dealer_hands: usize,
+    dealer_busts: usize,
+    even_money_offers: usize,
+    even_money_taken: usize,
+    /// Largest bet placed in any run
+    max_bet: isize,
+    capped_bets: usize,
+    table_max_caps: usize,
+    /// Longest betting sequence reached in any run
+    longest_sequence: usize,
+    /// Longest streaks of any run
+    longest_win_streak: usize,
+    longest_loss_streak: usize,
+    mistakes: MistakeReport,
 }
 
 impl TotalRunStats {
     pub fn new(starting_credits: isize) -> Self {
         TotalRunStats {
-            starting_credits: starting_credits,
+            starting_credits,
             num_runs: 0,
             num_games: 0,
             wins: 0,
             losses: 0,
             pushes: 0,
+            surrenders: 0,
+            split_rounds: 0,
+            split_hands: 0,
             total_credits: 0,
+            total_credits_squared: 0.0,
+            total_peak_credits: 0,
+            total_low_credits: 0,
             num_walk_away_with_more: 0,
+            num_busted_out: 0,
+            num_hit_target: 0,
+            num_stop_loss: 0,
+            insurance_wins: 0,
+            insurance_losses: 0,
+            insurance_net: 0,
+            dealer_hands: 0,
+            dealer_busts: 0,
+            even_money_offers: 0,
+            even_money_taken: 0,
+            max_bet: 0,
+            capped_bets: 0,
+            table_max_caps: 0,
+            longest_sequence: 0,
+            longest_win_streak: 0,
+            longest_loss_streak: 0,
+            mistakes: MistakeReport::new(),
         }
     }
 
     /// Adds the statistics for 1 simulated run
-    pub fn add_run(&mut self, run: RunStats) {
+    pub fn add_run(&mut self, run: &RunStats) {
         self.num_runs += 1;
         self.num_games += run.num_games;
         self.wins += run.wins;
         self.losses += run.losses;
         self.pushes += run.pushes;
+        self.surrenders += run.surrenders;
+        self.split_rounds += run.split_rounds;
+        self.split_hands += run.split_hands;
         self.total_credits += run.remaining_credits;
+        self.total_credits_squared += (run.remaining_credits as f64).powi(2);
+        self.total_peak_credits += run.peak_credits.unwrap_or(run.remaining_credits);
+        self.total_low_credits += run.low_credits.unwrap_or(run.remaining_credits);
+        self.insurance_wins += run.insurance_wins;
+        self.insurance_losses += run.insurance_losses;
+        self.insurance_net += run.insurance_net;
+        self.dealer_hands += run.dealer_hands;
+        self.dealer_busts += run.dealer_busts;
+        self.even_money_offers += run.even_money_offers;
+        self.even_money_taken += run.even_money_taken;
+        self.capped_bets += run.capped_bets;
+        self.table_max_caps += run.table_max_caps;
+        self.max_bet = self.max_bet.max(run.max_bet);
+        self.longest_sequence = self.longest_sequence.max(run.longest_sequence);
+        self.longest_win_streak = self.longest_win_streak.max(run.longest_win_streak);
+        self.longest_loss_streak = self.longest_loss_streak.max(run.longest_loss_streak);
+        self.mistakes.merge(&run.mistakes);
         if run.remaining_credits > self.starting_credits {
             self.num_walk_away_with_more += 1;
         }
+        match run.session_end {
+            SessionEnd::OutOfHands => (),
+            SessionEnd::Busted => self.num_busted_out += 1,
+            SessionEnd::Target => self.num_hit_target += 1,
+            SessionEnd::StopLoss => self.num_stop_loss += 1,
+        }
+    }
+}
+
+impl TotalRunStats {
+    /// Number of runs aggregated
+    pub fn num_runs(&self) -> usize {
+        self.num_runs
+    }
+
+    /// Number of games played over every run
+    pub fn num_games(&self) -> usize {
+        self.num_games
+    }
+
+    /// Average credits a player walks away with
+    pub fn avg_credits(&self) -> f64 {
+        self.total_credits as f64 / self.num_runs as f64
+    }
+
+    /// Sample variance of the credits players walk away with. Undefined (`None`) for fewer than 2 runs.
+    pub fn credits_variance(&self) -> Option<f64> {
+        if self.num_runs < 2 {
+            return None;
+        }
+        let n = self.num_runs as f64;
+        let mean = self.avg_credits();
+        // Rounding can push a variance of 0 slightly negative
+        Some(((self.total_credits_squared - n * mean * mean) / (n - 1.0)).max(0.0))
+    }
+
+    /// Sample standard deviation of the credits players walk away with, for at least 2 runs
+    pub fn credits_std_dev(&self) -> Option<f64> {
+        self.credits_variance().map(f64::sqrt)
+    }
+
+    /// Average of the most credits a player had during a run
+    pub fn avg_peak_credits(&self) -> f64 {
+        self.total_peak_credits as f64 / self.num_runs as f64
+    }
+
+    /// Average of the fewest credits a player had during a run
+    pub fn avg_low_credits(&self) -> f64 {
+        self.total_low_credits as f64 / self.num_runs as f64
+    }
+
+    /// Structured summary of every run, i.e. for notebooks and dashboards
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> serde_json::Value {
+        let percent_of_games = |count: usize| 100f64 * count as f64 / self.num_games as f64;
+        serde_json::json!({
+            "runs": self.num_runs,
+            "games": self.num_games,
+            "wins": self.wins,
+            "losses": self.losses,
+            "pushes": self.pushes,
+            "win_percent": percent_of_games(self.wins),
+            "loss_percent": percent_of_games(self.losses),
+            "push_percent": percent_of_games(self.pushes),
+            "surrenders": self.surrenders,
+            "surrender_percent": percent_of_games(self.surrenders),
+            "split_rounds": self.split_rounds,
+            "split_hands": self.split_hands,
+            "avg_credits": self.avg_credits(),
+            "credits_variance": self.credits_variance(),
+            "credits_std_dev": self.credits_std_dev(),
+            "busted_out": self.num_busted_out,
+            "hit_target": self.num_hit_target,
+            "hit_stop_loss": self.num_stop_loss,
+            "max_bet": self.max_bet,
+            "longest_win_streak": self.longest_win_streak,
+            "longest_loss_streak": self.longest_loss_streak,
+            "dealer_bust_rate": self.dealer_bust_rate(),
+            "bust_out_rate": self.bust_out_rate(),
+        })
+    }
+
+    /// Fraction of the hands the dealer played out that busted
+    pub fn dealer_bust_rate(&self) -> f64 {
+        self.dealer_busts as f64 / self.dealer_hands as f64
+    }
+
+    /// Fraction of runs where the player went broke
+    pub fn bust_out_rate(&self) -> f64 {
+        self.num_busted_out as f64 / self.num_runs as f64
+    }
+
+    /// Fraction of games that ended in a push (the stake is returned, no money changes hands)
+    pub fn push_rate(&self) -> f64 {
+        self.pushes as f64 / self.num_games as f64
+    }
+
+    /// Fraction of decided (not pushed) games that the player won
+    pub fn decided_win_rate(&self) -> f64 {
+        self.wins as f64 / (self.wins + self.losses) as f64
+    }
+
+    /// Number of games that actually won or lost money, i.e. games that did not push
+    pub fn effective_hands(&self) -> usize {
+        self.num_games - self.pushes
     }
 }
 
@@ -101,7 +592,7 @@ impl fmt::Display for TotalRunStats {
         let win_percent = 100f64 * (self.wins as f64 / self.num_games as f64);
         let loss_percent = 100f64 * (self.losses as f64 / self.num_games as f64);
         let push_percent = 100f64 * (self.pushes as f64 / self.num_games as f64);
-        let avg_credits = self.total_credits as f64 / self.num_runs as f64;
+        let avg_credits = self.avg_credits();
 
         // Display stats
         writeln!(
@@ -116,6 +607,474 @@ impl fmt::Display for TotalRunStats {
             avg_credits, self.num_walk_away_with_more,
         )
         .expect("I/O Error");
+        match (self.credits_std_dev(), self.credits_variance()) {
+            (Some(std_dev), Some(variance)) => writeln!(
+                f,
+                "Ending amount std dev: ${:.2} | Variance: {:.2}",
+                std_dev, variance,
+            ),
+            _ => writeln!(f, "Ending amount std dev: n/a (needs at least 2 runs)"),
+        }
+        .expect("I/O Error");
+        writeln!(
+            f,
+            "Avg peak amount: ${:.2} | Avg low amount: ${:.2}",
+            self.avg_peak_credits(),
+            self.avg_low_credits(),
+        )
+        .expect("I/O Error");
+        writeln!(
+            f,
+            "Busted out: {} times ({:.2}%)",
+            self.num_busted_out,
+            100f64 * self.bust_out_rate(),
+        )
+        .expect("I/O Error");
+        // Only players with a walk-away rule leave early with credits left
+        if self.num_hit_target + self.num_stop_loss > 0 {
+            writeln!(
+                f,
+                "Sessions ended: {} at the target | {} at the stop-loss | {} busted | {} out of hands",
+                self.num_hit_target,
+                self.num_stop_loss,
+                self.num_busted_out,
+                self.num_runs - self.num_hit_target - self.num_stop_loss - self.num_busted_out,
+            )
+            .expect("I/O Error");
+        }
+        writeln!(
+            f,
+            "Push rate: {:.2}% | Effective hands (money changed hands): {}",
+            100f64 * self.push_rate(),
+            self.effective_hands(),
+        )
+        .expect("I/O Error");
+        // Rounds resolved at the deal don't have the dealer play
+        if self.dealer_hands > 0 {
+            writeln!(
+                f,
+                "Dealer bust rate: {:.2}%",
+                100f64 * self.dealer_bust_rate()
+            )
+            .expect("I/O Error");
+        }
+        // Only shown when surrendering is allowed, and the player took it
+        if self.surrenders > 0 {
+            writeln!(
+                f,
+                "Surrendered: {} times ({:.2}% of games, counted as losses)",
+                self.surrenders,
+                100f64 * self.surrenders as f64 / self.num_games as f64,
+            )
+            .expect("I/O Error");
+        }
+        // Only shown when the player split a pair
+        if self.split_rounds > 0 {
+            writeln!(
+                f,
+                "Split: {} rounds into {} hands (each hand counted as a game)",
+                self.split_rounds, self.split_hands,
+            )
+            .expect("I/O Error");
+        }
+        // Only strategies that take insurance report it
+        if self.insurance_wins + self.insurance_losses > 0 {
+            writeln!(
+                f,
+                "Insurance W/L: {}/{} | Insurance net: ${}",
+                self.insurance_wins, self.insurance_losses, self.insurance_net,
+            )
+            .expect("I/O Error");
+        }
+        // Only shown when a player was dealt a natural against a dealer Ace
+        if self.even_money_offers > 0 {
+            writeln!(
+                f,
+                "Even money taken: {} of {} offers",
+                self.even_money_taken, self.even_money_offers,
+            )
+            .expect("I/O Error");
+        }
+        writeln!(f, "Largest bet: ${}", self.max_bet).expect("I/O Error");
+        writeln!(
+            f,
+            "Longest win streak: {} | Longest loss streak: {}",
+            self.longest_win_streak, self.longest_loss_streak,
+        )
+        .expect("I/O Error");
+        // Only progressive betting strategies run into the credits available
+        if self.capped_bets > 0 {
+            writeln!(f, "Bets capped by credits: {} times", self.capped_bets).expect("I/O Error");
+        }
+        // Only shown when the table has a maximum that got in the way
+        if self.table_max_caps > 0 {
+            writeln!(
+                f,
+                "Bets capped by the table max: {} times",
+                self.table_max_caps
+            )
+            .expect("I/O Error");
+        }
+        // Only sequence betting strategies keep a sequence
+        if self.longest_sequence > 0 {
+            writeln!(f, "Longest betting sequence: {}", self.longest_sequence).expect("I/O Error");
+        }
+        // Only audited runs report mistakes
+        if self.mistakes.decisions() > 0 {
+            write!(f, "{}", self.mistakes).expect("I/O Error");
+        }
         Ok(())
     }
 }
+
+/// Widest bar of a credit histogram, in characters
+const HISTOGRAM_BAR_WIDTH: usize = 40;
+
+/// Distribution of the credits players walked away with, bucketed into bins of equal width
+pub struct CreditHistogram {
+    bin_width: usize,
+    /// Number of runs ending in each bin, starting from $0
+    counts: Vec<usize>,
+}
+
+impl CreditHistogram {
+    /// Buckets the ending credits of every run into bins `bin_width` credits wide
+    pub fn new(runs: &[RunStats], bin_width: usize) -> Self {
+        let bin_width = bin_width.max(1);
+        let mut counts = Vec::new();
+        for run in runs {
+            let bin = run.remaining_credits.max(0) as usize / bin_width;
+            if bin >= counts.len() {
+                counts.resize(bin + 1, 0);
+            }
+            counts[bin] += 1;
+        }
+        CreditHistogram { bin_width, counts }
+    }
+
+    /// Number of runs ending in each bin
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+}
+
+impl fmt::Display for CreditHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let labels: Vec<String> = (0..self.counts.len())
+            .map(|bin| {
+                let low = bin * self.bin_width;
+                format!("${}-{}", low, low + self.bin_width - 1)
+            })
+            .collect();
+        let label_width = labels.iter().map(String::len).max().unwrap_or(0);
+        let most = self.counts.iter().copied().max().unwrap_or(0).max(1);
+        for (label, count) in labels.iter().zip(&self.counts) {
+            // Bins with any runs always get at least one mark
+            let bar = (count * HISTOGRAM_BAR_WIDTH).div_ceil(most);
+            writeln!(
+                f,
+                "{:>width$}: {} ({})",
+                label,
+                "#".repeat(bar),
+                count,
+                width = label_width
+            )
+            .expect("I/O Error");
+        }
+        Ok(())
+    }
+}
+
+/// A W-L-W sequence at one unit produces the cumulative series [1, 0, 1]
+#[rstest]
+fn net_units_series() {
+    let mut stats = RunStats::new();
+    stats.record_match_end(Outcome::Win);
+    stats.record_match_end(Outcome::Loss);
+    stats.record_match_end(Outcome::Win);
+    assert_eq!(stats.net_units(), &[1, 0, 1])
+}
+
+/// Pushes are excluded from the effective hand count
+#[rstest]
+fn push_rate_and_effective_hands() {
+    let mut total = TotalRunStats::new(100);
+    for outcomes in [
+        vec![Outcome::Win, Outcome::Push, Outcome::Loss, Outcome::Push],
+        vec![Outcome::Push, Outcome::Win, Outcome::Win, Outcome::Loss],
+    ] {
+        let mut run = RunStats::new();
+        for outcome in outcomes {
+            run.record_match_end(outcome);
+        }
+        total.add_run(&run);
+    }
+    assert_eq!(total.push_rate(), 3.0 / 8.0);
+    assert_eq!(total.effective_hands(), 5);
+    assert_eq!(total.decided_win_rate(), 3.0 / 5.0);
+}
+
+/// Streaks reset when the outcome changes. Pushes are skipped over unless they end streaks.
+#[rstest]
+#[case(false, 3, 2)]
+#[case(true, 2, 2)]
+fn win_and_loss_streaks(
+    #[case] pushes_end_streaks: bool,
+    #[case] win_streak: usize,
+    #[case] loss_streak: usize,
+) {
+    let mut run = RunStats::new();
+    run.set_pushes_end_streaks(pushes_end_streaks);
+    for outcome in [
+        Outcome::Win,
+        Outcome::BlackJack,
+        Outcome::Push,
+        Outcome::Win,
+        Outcome::Loss,
+        Outcome::Surrender,
+        Outcome::Win,
+    ] {
+        run.record_match_end(outcome);
+    }
+    assert_eq!(run.longest_win_streak(), win_streak);
+    assert_eq!(run.longest_loss_streak(), loss_streak);
+    let mut total = TotalRunStats::new(100);
+    total.add_run(&run);
+    assert!(total.to_string().contains(&format!(
+        "Longest win streak: {} | Longest loss streak: {}",
+        win_streak, loss_streak
+    )));
+}
+
+/// Ending credits are bucketed from $0, and every bin up to the richest run is listed
+#[rstest]
+fn credit_histogram() {
+    let runs: Vec<RunStats> = [0, 10, 30, 60, 55, 70]
+        .iter()
+        .map(|credits| {
+            let mut run = RunStats::new();
+            run.record_credits(*credits);
+            run
+        })
+        .collect();
+    let histogram = CreditHistogram::new(&runs, 25);
+    assert_eq!(histogram.counts(), &[2, 1, 3]);
+    assert_eq!(
+        histogram.to_string(),
+        format!(
+            " $0-24: {} (2)\n$25-49: {} (1)\n$50-74: {} (3)\n",
+            "#".repeat(27),
+            "#".repeat(14),
+            "#".repeat(40)
+        )
+    );
+}
+
+/// Only dealer hands over 21 count as busts
+#[rstest]
+fn dealer_bust_rate() {
+    let mut run = RunStats::new();
+    for final_value in [22, 17, 26, 21] {
+        run.record_dealer_hand(final_value);
+    }
+    let mut total = TotalRunStats::new(100);
+    total.add_run(&run);
+    assert_eq!(total.dealer_bust_rate(), 0.5);
+    assert!(total.to_string().contains("Dealer bust rate: 50.00%"));
+}
+
+/// Surrenders are tallied on their own, as well as with the losses
+#[rstest]
+fn surrender_results() {
+    let mut run = RunStats::new();
+    for outcome in [
+        Outcome::Surrender,
+        Outcome::Loss,
+        Outcome::Win,
+        Outcome::Surrender,
+    ] {
+        run.record_match_end(outcome);
+    }
+    assert_eq!(run.surrenders(), 2);
+    assert_eq!(run.losses(), 3);
+    assert_eq!(
+        run.to_string(),
+        "Games: 4 | W/L/P: 1/3/0 | Surrenders: 2 | Credits: $0"
+    );
+    let mut total = TotalRunStats::new(100);
+    total.add_run(&run);
+    assert_eq!(total.surrenders, 2);
+    assert!(total
+        .to_string()
+        .contains("Surrendered: 2 times (50.00% of games, counted as losses)"));
+}
+
+/// Every hand of a split round counts as a game, and the split itself is tallied on its own
+#[rstest]
+fn split_results() {
+    let mut run = RunStats::new();
+    run.record_match_end(Outcome::Loss);
+    run.record_split_result(&[Outcome::Win, Outcome::Push, Outcome::Win]);
+    assert_eq!(run.num_games(), 4);
+    assert_eq!(run.wins(), 2);
+    assert_eq!(run.split_rounds(), 1);
+    assert_eq!(run.net_units(), &[-1, 0, 0, 1]);
+    assert_eq!(run.to_string(), "Games: 4 | W/L/P: 2/1/1 | Credits: $0");
+    let mut total = TotalRunStats::new(100);
+    total.add_run(&run);
+    total.add_run(&run);
+    assert_eq!(total.split_hands, 6);
+    assert!(total
+        .to_string()
+        .contains("Split: 2 rounds into 6 hands (each hand counted as a game)"));
+}
+
+/// Insurance results are tracked separately from the main bet
+#[rstest]
+fn insurance_results() {
+    let mut run = RunStats::new();
+    run.record_insurance(5, 15);
+    run.record_insurance(5, 0);
+    run.record_insurance(5, 0);
+    let mut total = TotalRunStats::new(100);
+    total.add_run(&run);
+    assert_eq!(total.insurance_wins, 1);
+    assert_eq!(total.insurance_losses, 2);
+    assert_eq!(total.insurance_net, 0);
+}
+
+/// The largest bet of any run is kept
+#[rstest]
+fn largest_bet() {
+    let mut first = RunStats::new();
+    first.record_bet(5);
+    first.record_bet(40);
+    first.record_bet(10);
+    let mut second = RunStats::new();
+    second.record_bet(20);
+    assert_eq!(first.max_bet(), 40);
+    let mut total = TotalRunStats::new(100);
+    total.add_run(&first);
+    total.add_run(&second);
+    assert_eq!(total.max_bet, 40);
+    assert!(total.to_string().contains("Largest bet: $40"));
+}
+
+/// Even money offers and takes are added up across runs
+#[rstest]
+fn even_money_results() {
+    let mut run = RunStats::new();
+    run.record_even_money(false);
+    run.record_even_money(true);
+    let mut total = TotalRunStats::new(100);
+    total.add_run(&run);
+    total.add_run(&run);
+    assert_eq!(total.even_money_offers, 4);
+    assert_eq!(total.even_money_taken, 2);
+    assert!(total
+        .to_string()
+        .contains("Even money taken: 2 of 4 offers"));
+}
+
+/// Snapshots track the highest and lowest credits of a run, which are averaged across runs
+#[rstest]
+fn credit_snapshots() {
+    let mut stats = RunStats::new();
+    assert_eq!(stats.peak_credits(), None);
+    for credits in [100, 103, 95, 98] {
+        stats.record_credit_snapshot(credits);
+    }
+    stats.record_credits(98);
+    assert_eq!(stats.peak_credits(), Some(103));
+    assert_eq!(stats.low_credits(), Some(95));
+
+    let mut other = RunStats::new();
+    other.record_credit_snapshot(100);
+    other.record_credit_snapshot(107);
+    other.record_credits(107);
+
+    let mut total = TotalRunStats::new(100);
+    total.add_run(&stats);
+    total.add_run(&other);
+    assert_eq!(total.avg_peak_credits(), 105.0);
+    assert_eq!(total.avg_low_credits(), 97.5);
+}
+
+/// Runs that end early are counted by how they ended
+#[rstest]
+fn session_endings() {
+    let mut total = TotalRunStats::new(100);
+    for session_end in [
+        SessionEnd::Target,
+        SessionEnd::Target,
+        SessionEnd::StopLoss,
+        SessionEnd::Busted,
+        SessionEnd::OutOfHands,
+    ] {
+        let mut run = RunStats::new();
+        run.record_session_end(session_end);
+        assert_eq!(run.session_end(), session_end);
+        total.add_run(&run);
+    }
+    assert!(total.to_string().contains(
+        "Sessions ended: 2 at the target | 1 at the stop-loss | 1 busted | 1 out of hands"
+    ));
+}
+
+/// Runs that went broke are counted as bust-outs
+#[rstest]
+fn bust_out_rate() {
+    let mut total = TotalRunStats::new(100);
+    for busted in [true, false, false, false] {
+        let mut run = RunStats::new();
+        if busted {
+            run.record_bust();
+        }
+        assert_eq!(run.is_busted(), busted);
+        total.add_run(&run);
+    }
+    assert_eq!(total.bust_out_rate(), 0.25);
+    assert!(total.to_string().contains("Busted out: 1 times (25.00%)"));
+}
+
+/// The spread of ending credits needs at least 2 runs
+#[rstest]
+fn credits_variance() {
+    let mut total = TotalRunStats::new(100);
+    let mut run = RunStats::new();
+    run.record_credits(90);
+    total.add_run(&run);
+    assert_eq!(total.credits_variance(), None);
+    assert!(total.to_string().contains("std dev: n/a"));
+
+    for credits in [100, 110] {
+        let mut run = RunStats::new();
+        run.record_credits(credits);
+        total.add_run(&run);
+    }
+    assert!((total.credits_variance().unwrap() - 100.0).abs() < 1e-9);
+    assert!((total.credits_std_dev().unwrap() - 10.0).abs() < 1e-9);
+}
+
+/// The JSON summary carries the totals, with no variance for a single run
+#[cfg(feature = "json")]
+#[rstest]
+fn total_run_stats_json() {
+    let mut run = RunStats::new();
+    run.record_match_end(Outcome::Win);
+    run.record_match_end(Outcome::Loss);
+    run.record_credits(100);
+    let mut total = TotalRunStats::new(100);
+    total.add_run(&run);
+
+    let json = total.to_json();
+    assert_eq!(json["runs"], 1);
+    assert_eq!(json["games"], 2);
+    assert_eq!(json["win_percent"], 50.0);
+    assert_eq!(json["avg_credits"], 100.0);
+    assert!(json["credits_variance"].is_null());
+
+    let run_json = serde_json::to_value(&run).unwrap();
+    assert_eq!(run_json["wins"], 1);
+    assert_eq!(run_json["remaining_credits"], 100);
+}